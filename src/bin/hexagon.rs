@@ -0,0 +1,58 @@
+use raytra::tup::{Point, Vector};
+use raytra::sphere::Sphere;
+use raytra::cylinder::Cylinder;
+use raytra::group::Group;
+use raytra::color::{self, Color};
+use raytra::material::Material;
+use raytra::light::PointLight;
+use raytra::world::World;
+use raytra::camera::Camera;
+use raytra::matrix::Matrix4x4;
+use raytra::transform::*;
+
+use std::f32::consts::PI;
+use std::fs;
+
+fn hexagon_side(rotation: f32, material: &Material) -> Group {
+    let corner = Sphere::new(Matrix4x4::identity()
+                             .scale(0.25, 0.25, 0.25)
+                             .translate(0.0, 0.0, -1.0))
+        .with_material(material.clone());
+
+    let edge = Cylinder::new(Matrix4x4::identity()
+                             .scale(0.25, 1.0, 0.25)
+                             .rotate_z(-PI / 2.0)
+                             .rotate_y(-PI / 6.0)
+                             .translate(0.0, 0.0, -1.0))
+        .with_bounds(0.0, 1.0)
+        .with_material(material.clone());
+
+    Group::new(rotation_y(rotation))
+        .with_child(corner)
+        .with_child(edge)
+}
+
+fn main() -> std::io::Result<()> {
+    const CANVAS_SIZE: usize = 200;
+
+    let material = Material {
+        color: Color::new(0.8, 0.5, 0.3),
+        ..Material::default()
+    };
+
+    let mut hexagon = Group::new(Matrix4x4::identity().rotate_x(-PI / 6.0));
+    for n in 0..6 {
+        hexagon.add_child(Box::new(hexagon_side(n as f32 * PI / 3.0, &material)));
+    }
+
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), color::WHITE);
+    let world = World::new(vec![Box::new(hexagon)], vec![light]);
+
+    let camera = Camera::new(CANVAS_SIZE, CANVAS_SIZE, PI / 3.0,
+                             view_transform(Point::new(0.0, 0.0, -3.5),
+                                            Point::new(0.0, 0.0, 0.0),
+                                            Vector::new(0.0, 1.0, 0.0)));
+
+    fs::write("hexagon.ppm", camera.render(&world).to_ppm())?;
+    Ok(())
+}
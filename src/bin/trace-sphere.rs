@@ -1,41 +1,32 @@
-use raytra::canvas::Canvas;
-use raytra::tup::Point;
+use raytra::tup::{Point, Vector};
 use raytra::sphere::Sphere;
-use raytra::rays::Ray;
-use raytra::color;
-
+use raytra::color::{self, Color};
+use raytra::material::Material;
+use raytra::light::PointLight;
+use raytra::world::World;
+use raytra::camera::Camera;
+use raytra::transform::view_transform;
+
+use std::f32::consts::PI;
 use std::fs;
 
 fn main() -> std::io::Result<()> {
-    const WALL_SIZE: usize = 7;
     const CANVAS_SIZE: usize = 100;
 
-    let pixel_size = WALL_SIZE as f32 / CANVAS_SIZE as f32;
-    let half = WALL_SIZE as f32 / 2.0;
-
-    let light = Point::new(0.0, 0.0, -5.0);
-    let wall_z = 10.0;
-
-    let mut canvas = Canvas::new(CANVAS_SIZE, CANVAS_SIZE);
-    let shape = Sphere::unit();
-
-    for y in 0..CANVAS_SIZE {
-        let world_y = half - pixel_size * (y as f32);
-
-        for x in 0..CANVAS_SIZE {
-            let world_x = -half + pixel_size * (x as f32);
-
-            let pos = Point::new(world_x as f32, world_y as f32, wall_z);
+    let material = Material {
+        color: Color::new(1.0, 0.2, 1.0),
+        ..Material::default()
+    };
+    let shape = Sphere::unit().with_material(material);
 
-            let r = Ray::new(light, (pos - light).norm());
-            let xs = shape.intersect(r);
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), color::WHITE);
+    let world = World::new(vec![Box::new(shape)], vec![light]);
 
-            if let Some(_) = xs.hit() {
-                canvas.write_at(x, y, color::RED);
-            }
-        }
-    }
+    let camera = Camera::new(CANVAS_SIZE, CANVAS_SIZE, PI / 3.0,
+                             view_transform(Point::new(0.0, 0.0, -5.0),
+                                            Point::new(0.0, 0.0, 0.0),
+                                            Vector::new(0.0, 1.0, 0.0)));
 
-    fs::write("sphere.ppm", canvas.to_ppm())?;
+    fs::write("sphere.ppm", camera.render(&world).to_ppm())?;
     Ok(())
 }
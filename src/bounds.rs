@@ -0,0 +1,235 @@
+use crate::matrix::Matrix4x4;
+use crate::rays::Ray;
+use crate::tup::Point;
+
+// An axis-aligned bounding box. Unbounded shapes such as planes use
+// infinite extents; an empty box has min > max.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+fn axis(p: Point, i: usize) -> f32 {
+    match i {
+        0 => p.x(),
+        1 => p.y(),
+        _ => p.z(),
+    }
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Bounds {
+        Bounds { min, max }
+    }
+
+    pub fn empty() -> Bounds {
+        Bounds::new(Point::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+                    Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY))
+    }
+
+    pub fn from_points(points: &[Point]) -> Bounds {
+        points.iter().fold(Bounds::empty(), |b, p| b.with_point(*p))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| axis(self.min, i) > axis(self.max, i))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        !self.is_empty() &&
+            (0..3).all(|i| axis(self.min, i).is_finite() && axis(self.max, i).is_finite())
+    }
+
+    pub fn with_point(self, p: Point) -> Bounds {
+        Bounds::new(Point::new(self.min.x().min(p.x()),
+                               self.min.y().min(p.y()),
+                               self.min.z().min(p.z())),
+                    Point::new(self.max.x().max(p.x()),
+                               self.max.y().max(p.y()),
+                               self.max.z().max(p.z())))
+    }
+
+    pub fn merge(self, other: Bounds) -> Bounds {
+        if other.is_empty() {
+            return self;
+        }
+
+        self.with_point(other.min).with_point(other.max)
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (0..3).all(|i| axis(self.min, i) <= axis(p, i) && axis(p, i) <= axis(self.max, i))
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new((self.min.x() + self.max.x()) / 2.0,
+                   (self.min.y() + self.max.y()) / 2.0,
+                   (self.min.z() + self.max.z()) / 2.0)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let dx = self.max.x() - self.min.x();
+        let dy = self.max.y() - self.min.y();
+        let dz = self.max.z() - self.min.z();
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    pub fn axis_min(&self, i: usize) -> f32 {
+        axis(self.min, i)
+    }
+
+    pub fn axis_max(&self, i: usize) -> f32 {
+        axis(self.max, i)
+    }
+
+    // The box around this one after transforming it by `m`, built one
+    // axis at a time from the matrix entries (Arvo's method) rather than
+    // from the eight corners, so infinite extents stay well defined.
+    pub fn transform(&self, m: &Matrix4x4) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+
+        for r in 0..3 {
+            min[r] = m.elem[r][3];
+            max[r] = m.elem[r][3];
+
+            for c in 0..3 {
+                let e = m.elem[r][c];
+                if e == 0.0 {
+                    continue;
+                }
+
+                let a = e * axis(self.min, c);
+                let b = e * axis(self.max, c);
+                min[r] += a.min(b);
+                max[r] += a.max(b);
+            }
+        }
+
+        Bounds::new(Point::new(min[0], min[1], min[2]),
+                    Point::new(max[0], max[1], max[2]))
+    }
+
+    // Slab test: whether the ray passes through the box anywhere along
+    // its length, in either direction.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+
+        for i in 0..3 {
+            let origin = axis(ray.origin, i);
+            let direction = match i {
+                0 => ray.direction.x(),
+                1 => ray.direction.y(),
+                _ => ray.direction.z(),
+            };
+
+            if direction == 0.0 {
+                if origin < axis(self.min, i) || origin > axis(self.max, i) {
+                    return false;
+                }
+                continue;
+            }
+
+            let t1 = (axis(self.min, i) - origin) / direction;
+            let t2 = (axis(self.max, i) - origin) / direction;
+            tmin = tmin.max(t1.min(t2));
+            tmax = tmax.min(t1.max(t2));
+        }
+
+        tmin <= tmax
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::*;
+    use crate::tup::Vector;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn empty() {
+        let b = Bounds::empty();
+
+        assert!(b.is_empty());
+        assert!(!b.is_finite());
+        assert_eq!(b.surface_area(), 0.0);
+        assert!(b.merge(Bounds::empty()).is_empty());
+    }
+
+    #[test]
+    fn points() {
+        let b = Bounds::from_points(&[Point::new(-5.0, 2.0, 0.0),
+                                      Point::new(7.0, 0.0, -3.0)]);
+
+        assert_eq!(b.min, Point::new(-5.0, 0.0, -3.0));
+        assert_eq!(b.max, Point::new(7.0, 2.0, 0.0));
+        assert_eq!(b.centroid(), Point::new(1.0, 1.0, -1.5));
+        assert_eq!(b.surface_area(), 2.0 * (12.0 * 2.0 + 2.0 * 3.0 + 3.0 * 12.0));
+        assert!(b.contains(Point::new(5.0, 1.0, -2.0)));
+        assert!(!b.contains(Point::new(5.0, 3.0, -2.0)));
+    }
+
+    #[test]
+    fn merge() {
+        let b1 = Bounds::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b2 = Bounds::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        let b = b1.merge(b2);
+
+        assert_eq!(b.min, Point::new(-5.0, -7.0, -2.0));
+        assert_eq!(b.max, Point::new(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn transform() {
+        let b = Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let m = Matrix4x4::identity().rotate_y(PI / 4.0).rotate_x(PI / 4.0);
+        let t = b.transform(&m);
+        let a = 2.0_f32.sqrt();
+
+        assert_eq!(t.min, Point::new(-a, -1.0 - a / 2.0, -1.0 - a / 2.0));
+        assert_eq!(t.max, Point::new(a, 1.0 + a / 2.0, 1.0 + a / 2.0));
+
+        let b = Bounds::new(Point::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+                            Point::new(f32::INFINITY, 0.0, f32::INFINITY));
+        let t = b.transform(&translation(0.0, 2.0, 0.0));
+        assert_eq!(t.min.y(), 2.0);
+        assert_eq!(t.max.y(), 2.0);
+        assert!(!t.is_finite());
+    }
+
+    #[test]
+    fn intersects() {
+        let b = Bounds::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (Point::new(15.0, 1.0, 2.0), Vector::new(-1.0, 0.0, 0.0), true),
+            (Point::new(-5.0, -1.0, 4.0), Vector::new(1.0, 0.0, 0.0), true),
+            (Point::new(7.0, 6.0, 5.0), Vector::new(0.0, -1.0, 0.0), true),
+            (Point::new(9.0, -5.0, 6.0), Vector::new(0.0, 1.0, 0.0), true),
+            (Point::new(8.0, 2.0, 12.0), Vector::new(0.0, 0.0, -1.0), true),
+            (Point::new(6.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(8.0, 1.0, 3.5), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(9.0, -1.0, -8.0), Vector::new(2.0, 4.0, 6.0), false),
+            (Point::new(8.0, 3.0, -4.0), Vector::new(6.0, 2.0, 4.0), false),
+            (Point::new(9.0, -1.0, -2.0), Vector::new(4.0, 6.0, 2.0), false),
+            (Point::new(4.0, 0.0, 9.0), Vector::new(0.0, 0.0, -1.0), false),
+            (Point::new(8.0, 6.0, -1.0), Vector::new(0.0, -1.0, 0.0), false),
+            (Point::new(12.0, 5.0, 4.0), Vector::new(-1.0, 0.0, 0.0), false),
+        ];
+
+        for (origin, direction, expected) in cases.iter() {
+            let r = Ray::new(*origin, direction.norm());
+            assert_eq!(b.intersects(&r), *expected, "{:?} {:?}", origin, direction);
+        }
+    }
+}
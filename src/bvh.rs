@@ -0,0 +1,266 @@
+use crate::bounds::Bounds;
+use crate::rays::Ray;
+use crate::tup::Point;
+
+const BINS: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+// Cost of visiting an interior node relative to intersecting one shape.
+const TRAVERSAL_COST: f32 = 0.125;
+
+#[derive(Debug)]
+enum Node {
+    Leaf { bounds: Bounds, start: usize, end: usize },
+    Interior { bounds: Bounds, left: usize, right: usize },
+}
+
+impl Node {
+    fn bounds(&self) -> &Bounds {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+// A bounding volume hierarchy over a list of shapes, identified by their
+// index in that list. Splits are chosen with the surface area heuristic,
+// evaluated over a fixed number of bins along the widest axis. Shapes
+// with infinite bounds are kept outside the tree and always visited.
+#[derive(Debug)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+    bounds: Bounds,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    bounds: Bounds,
+    count: usize,
+}
+
+impl Bvh {
+    pub fn new(bounds: &[Bounds]) -> Bvh {
+        let (mut indices, unbounded): (Vec<usize>, Vec<usize>) = (0..bounds.len())
+            .filter(|i| !bounds[*i].is_empty())
+            .partition(|i| bounds[*i].is_finite());
+
+        let mut bvh = Bvh {
+            nodes: vec![],
+            indices: vec![],
+            unbounded,
+            bounds: bounds.iter().fold(Bounds::empty(), |b, x| b.merge(*x)),
+        };
+
+        if !indices.is_empty() {
+            let centroids: Vec<_> = bounds.iter().map(|b| b.centroid()).collect();
+            let len = indices.len();
+            bvh.build(bounds, &centroids, &mut indices, 0, len);
+        }
+        bvh.indices = indices;
+
+        bvh
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    // Calls `visit` with the index of every shape whose box the ray
+    // passes through.
+    pub fn traverse(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for i in self.unbounded.iter() {
+            visit(*i);
+        }
+
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !node.bounds().intersects(ray) {
+                continue;
+            }
+
+            match node {
+                Node::Leaf { start, end, .. } => {
+                    for i in self.indices[*start..*end].iter() {
+                        visit(*i);
+                    }
+                }
+                Node::Interior { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+    }
+
+    // Builds the subtree over `indices[start..end]`, reordering that range
+    // so each leaf covers a contiguous slice, and returns its node index.
+    fn build(&mut self,
+             bounds: &[Bounds],
+             centroids: &[Point],
+             indices: &mut [usize],
+             start: usize,
+             end: usize) -> usize {
+        let node_bounds = indices[start..end].iter()
+            .fold(Bounds::empty(), |b, i| b.merge(bounds[*i]));
+        let centroid_bounds = indices[start..end].iter()
+            .fold(Bounds::empty(), |b, i| b.with_point(centroids[*i]));
+        let count = end - start;
+
+        let node = self.nodes.len();
+        self.nodes.push(Node::Leaf { bounds: node_bounds, start, end });
+
+        if count <= 1 {
+            return node;
+        }
+
+        let axis = (0..3)
+            .max_by(|a, b| {
+                let ea = centroid_bounds.axis_max(*a) - centroid_bounds.axis_min(*a);
+                let eb = centroid_bounds.axis_max(*b) - centroid_bounds.axis_min(*b);
+                ea.partial_cmp(&eb).unwrap()
+            })
+            .unwrap();
+        let lo = centroid_bounds.axis_min(axis);
+        let extent = centroid_bounds.axis_max(axis) - lo;
+
+        // All centroids coincide, so no split can separate them.
+        if extent <= 0.0 {
+            return node;
+        }
+
+        let bin_of = |i: usize| {
+            let c = match axis {
+                0 => centroids[i].x(),
+                1 => centroids[i].y(),
+                _ => centroids[i].z(),
+            };
+            (((c - lo) / extent * BINS as f32) as usize).min(BINS - 1)
+        };
+
+        let mut bins = [Bin { bounds: Bounds::empty(), count: 0 }; BINS];
+        for i in indices[start..end].iter() {
+            let bin = &mut bins[bin_of(*i)];
+            bin.bounds = bin.bounds.merge(bounds[*i]);
+            bin.count += 1;
+        }
+
+        // Cost of splitting after each bin: the area of each side weighted
+        // by how many shapes it holds.
+        let mut costs = [0.0; BINS - 1];
+        let mut left = Bin { bounds: Bounds::empty(), count: 0 };
+        for b in 0..BINS - 1 {
+            left.bounds = left.bounds.merge(bins[b].bounds);
+            left.count += bins[b].count;
+            costs[b] = left.bounds.surface_area() * left.count as f32;
+        }
+        let mut right = Bin { bounds: Bounds::empty(), count: 0 };
+        for b in (1..BINS).rev() {
+            right.bounds = right.bounds.merge(bins[b].bounds);
+            right.count += bins[b].count;
+            costs[b - 1] += right.bounds.surface_area() * right.count as f32;
+        }
+
+        let (split, cost) = costs.iter()
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap();
+        let area = node_bounds.surface_area();
+        let split_cost = if area > 0.0 { TRAVERSAL_COST + cost / area } else { 0.0 };
+
+        if count <= MAX_LEAF_SIZE && split_cost >= count as f32 {
+            return node;
+        }
+
+        let mut mid = start;
+        for i in start..end {
+            if bin_of(indices[i]) <= split {
+                indices.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        // Every shape fell on one side; split the range in half instead.
+        if mid == start || mid == end {
+            mid = start + count / 2;
+        }
+
+        let left = self.build(bounds, centroids, indices, start, mid);
+        let right = self.build(bounds, centroids, indices, mid, end);
+        self.nodes[node] = Node::Interior { bounds: node_bounds, left, right };
+
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tup::Vector;
+
+    fn unit_box(x: f32, y: f32, z: f32) -> Bounds {
+        Bounds::new(Point::new(x - 0.5, y - 0.5, z - 0.5),
+                    Point::new(x + 0.5, y + 0.5, z + 0.5))
+    }
+
+    fn visited(bvh: &Bvh, ray: Ray) -> Vec<usize> {
+        let mut v = vec![];
+        bvh.traverse(&ray, |i| v.push(i));
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = Bvh::new(&[]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(bvh.bounds().is_empty());
+        assert!(visited(&bvh, r).is_empty());
+    }
+
+    #[test]
+    fn traverse() {
+        let boxes: Vec<Bounds> = (0..100)
+            .map(|i| unit_box((i % 10) as f32 * 2.0, (i / 10) as f32 * 2.0, 0.0))
+            .collect();
+        let bvh = Bvh::new(&boxes);
+
+        assert_eq!(bvh.bounds().min, Point::new(-0.5, -0.5, -0.5));
+        assert_eq!(bvh.bounds().max, Point::new(18.5, 18.5, 0.5));
+
+        let r = Ray::new(Point::new(6.0, 4.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&bvh, r), vec![23]);
+
+        let r = Ray::new(Point::new(-5.0, 8.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(visited(&bvh, r), (40..50).collect::<Vec<_>>());
+
+        let r = Ray::new(Point::new(1.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(visited(&bvh, r).is_empty());
+    }
+
+    #[test]
+    fn unbounded() {
+        let plane = Bounds::new(Point::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+                                Point::new(f32::INFINITY, 0.0, f32::INFINITY));
+        let bvh = Bvh::new(&[unit_box(0.0, 5.0, 0.0), plane, Bounds::empty()]);
+        let r = Ray::new(Point::new(5.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(!bvh.bounds().is_finite());
+        assert_eq!(visited(&bvh, r), vec![1]);
+    }
+
+    #[test]
+    fn coincident() {
+        let boxes = vec![unit_box(1.0, 1.0, 1.0); 20];
+        let bvh = Bvh::new(&boxes);
+        let r = Ray::new(Point::new(1.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(visited(&bvh, r), (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::canvas::Canvas;
+use crate::matrix::Matrix4x4;
+use crate::rays::Ray;
+use crate::transform::Transform;
+use crate::tup::Point;
+use crate::world::{World, MAX_DEPTH};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f32,
+    transform: Transform,
+    half_width: f32,
+    half_height: f32,
+    pixel_size: f32,
+}
+
+impl Camera {
+    pub fn new(hsize: usize,
+               vsize: usize,
+               field_of_view: f32,
+               transform: Matrix4x4) -> Camera {
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f32 / vsize as f32;
+
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Transform::new(transform)
+                .expect("camera transform is not invertible"),
+            half_width,
+            half_height,
+            pixel_size: (half_width * 2.0) / hsize as f32,
+        }
+    }
+
+    // For view transforms that may not be invertible: build the camera
+    // with the identity, then pass the result of `Transform::new`.
+    pub fn with_transform(self, transform: Transform) -> Camera {
+        Camera {
+            transform,
+            ..self
+        }
+    }
+
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        // Offsets from the edge of the canvas to the pixel's center.
+        let xoffset = (px as f32 + 0.5) * self.pixel_size;
+        let yoffset = (py as f32 + 0.5) * self.pixel_size;
+
+        // The camera looks toward -z, so +x is to the left.
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let inv = self.transform.inverse();
+        let pixel = *inv * Point::new(world_x, world_y, -1.0);
+        let origin = *inv * Point::new(0.0, 0.0, 0.0);
+
+        Ray::new(origin, (pixel - origin).norm())
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                image.write_at(x, y, world.color_at(ray, MAX_DEPTH));
+            }
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::sphere::Sphere;
+    use crate::transform::*;
+    use crate::tup::Vector;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn new() {
+        let c = Camera::new(160, 120, PI / 2.0, Matrix4x4::identity());
+
+        assert_eq!(c.hsize, 160);
+        assert_eq!(c.vsize, 120);
+        assert_eq!(c.field_of_view, PI / 2.0);
+        assert_eq!(*c.transform.matrix(), Matrix4x4::identity());
+    }
+
+    #[test]
+    fn with_transform() {
+        let t = Transform::new(translation(0.0, -2.0, 5.0)).unwrap();
+        let c = Camera::new(160, 120, PI / 2.0, Matrix4x4::identity()).with_transform(t);
+
+        assert_eq!(c.transform, t);
+    }
+
+    #[test]
+    fn pixel_size() {
+        let c = Camera::new(200, 125, PI / 2.0, Matrix4x4::identity());
+        assert!(abs_diff_eq!(c.pixel_size, 0.01, epsilon=0.00001));
+
+        let c = Camera::new(125, 200, PI / 2.0, Matrix4x4::identity());
+        assert!(abs_diff_eq!(c.pixel_size, 0.01, epsilon=0.00001));
+    }
+
+    #[test]
+    fn ray_center() {
+        let c = Camera::new(201, 101, PI / 2.0, Matrix4x4::identity());
+        let r = c.ray_for_pixel(100, 50);
+
+        assert_eq!(r.origin, Point::new(0.0, 0.0, 0.0));
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_corner() {
+        let c = Camera::new(201, 101, PI / 2.0, Matrix4x4::identity());
+        let r = c.ray_for_pixel(0, 0);
+
+        assert_eq!(r.origin, Point::new(0.0, 0.0, 0.0));
+        assert_eq!(r.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn ray_transformed() {
+        let t = translation(0.0, -2.0, 5.0).rotate_y(PI / 4.0);
+        let c = Camera::new(201, 101, PI / 2.0, t);
+        let r = c.ray_for_pixel(100, 50);
+        let a = 2.0_f32.sqrt() / 2.0;
+
+        assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
+        assert_eq!(r.direction, Vector::new(a, 0.0, -a));
+    }
+
+    #[test]
+    fn render() {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+        let s1 = Sphere::unit().with_material(Material {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Material::default()
+        });
+        let s2 = Sphere::new(scaling(0.5, 0.5, 0.5));
+        let w = World::new(vec![Box::new(s1), Box::new(s2)], vec![light]);
+
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let c = Camera::new(11, 11, PI / 2.0, view_transform(from, to, up));
+        let image = c.render(&w);
+
+        assert_eq!(image.at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+}
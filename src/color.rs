@@ -1,6 +1,8 @@
 use std::cmp::PartialEq;
 use std::ops::{Add, Sub, Mul};
 
+use crate::EPSILON;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
     red: f32,
@@ -12,13 +14,25 @@ pub const RED: Color = Color{red: 1.0, blue: 0.0, green: 0.0};
 pub const BLUE: Color = Color{red: 0.0, blue: 1.0, green: 0.0};
 pub const GREEN: Color = Color{red: 0.0, blue: 0.0, green: 1.0};
 pub const WHITE: Color = Color{red: 1.0, blue: 1.0, green: 1.0};
-pub const BLACK: Color = Color{red: 0.0, blue: 0.0, green: 1.0};
+pub const BLACK: Color = Color{red: 0.0, blue: 0.0, green: 0.0};
 
 impl Color {
     pub fn new(red: f32, green: f32, blue: f32) -> Color {
         Color{red, green, blue}
     }
 
+    pub fn red(&self) -> f32 {
+        self.red
+    }
+
+    pub fn green(&self) -> f32 {
+        self.green
+    }
+
+    pub fn blue(&self) -> f32 {
+        self.blue
+    }
+
     pub fn clamp(&self) -> Color {
         let min = 0.0;
         let max = 1.0;
@@ -81,9 +95,9 @@ impl Mul<f32> for Color {
 
 impl PartialEq for Color {
     fn eq(&self, other: &Color) -> bool {
-        abs_diff_eq!(self.red, other.red) &&
-            abs_diff_eq!(self.green, other.green) &&
-            abs_diff_eq!(self.blue, other.blue)
+        abs_diff_eq!(self.red, other.red, epsilon=EPSILON) &&
+            abs_diff_eq!(self.green, other.green, epsilon=EPSILON) &&
+            abs_diff_eq!(self.blue, other.blue, epsilon=EPSILON)
     }
 }
 
@@ -115,4 +129,10 @@ mod tests {
         assert_eq!(a * b, Color::new(0.9, 0.2, 0.04));
 
     }
+
+    #[test]
+    fn constants() {
+        assert_eq!(BLACK, Color::new(0.0, 0.0, 0.0));
+        assert_eq!(WHITE, Color::new(1.0, 1.0, 1.0));
+    }
 }
@@ -0,0 +1,254 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::EPSILON;
+
+// A double-napped cone around the y axis in object space, with its apex at
+// the origin and radius |y| at height y. Truncated to
+// `minimum < y < maximum` and optionally capped at both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    transform: Transform,
+    material: Material,
+    minimum: f32,
+    maximum: f32,
+    closed: bool,
+}
+
+impl Cone {
+    pub fn new(transform: Matrix4x4) -> Cone {
+        Cone {
+            transform: Transform::new(transform)
+                .expect("cone transform is not invertible"),
+            material: Material::default(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Cone {
+        Cone {
+            material,
+            ..self
+        }
+    }
+
+    pub fn with_bounds(self, minimum: f32, maximum: f32) -> Cone {
+        Cone {
+            minimum,
+            maximum,
+            ..self
+        }
+    }
+
+    pub fn with_closed(self, closed: bool) -> Cone {
+        Cone {
+            closed,
+            ..self
+        }
+    }
+
+    fn push_if_in_bounds<'a>(&'a self, ray: Ray, t: f32, xs: &mut Vec<Intersection<'a>>) {
+        let y = ray.origin.y() + t * ray.direction.y();
+        if self.minimum < y && y < self.maximum {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y().abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum].iter() {
+            let t = (y - ray.origin.y()) / ray.direction.y();
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+// Whether the ray at `t` is within `radius` of the y axis.
+fn check_cap(ray: Ray, t: f32, radius: f32) -> bool {
+    let x = ray.origin.x() + t * ray.direction.x();
+    let z = ray.origin.z() + t * ray.direction.z();
+
+    (x.powi(2) + z.powi(2)) <= radius.powi(2) + EPSILON
+}
+
+impl Shape for Cone {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        let r = self.minimum.abs().max(self.maximum.abs());
+
+        Bounds::new(Point::new(-r, self.minimum, -r),
+                    Point::new(r, self.maximum, r))
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let mut xs = vec![];
+        let o = ray.origin;
+        let d = ray.direction;
+
+        let a = d.x().powi(2) - d.y().powi(2) + d.z().powi(2);
+        let b = 2.0 * o.x() * d.x() - 2.0 * o.y() * d.y() + 2.0 * o.z() * d.z();
+        let c = o.x().powi(2) - o.y().powi(2) + o.z().powi(2);
+
+        if a.abs() < EPSILON {
+            // The ray is parallel to one half of the cone, so it crosses
+            // the other half at most once.
+            if b.abs() >= EPSILON {
+                self.push_if_in_bounds(ray, -c / (2.0 * b), &mut xs);
+            }
+        } else {
+            // Rays grazing the surface can come out slightly negative.
+            let disc = b.powi(2) - 4.0 * a * c;
+            if disc < -EPSILON {
+                return Intersections::new(vec![]);
+            }
+            let disc = disc.max(0.0);
+
+            let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            self.push_if_in_bounds(ray, t0, &mut xs);
+            self.push_if_in_bounds(ray, t1, &mut xs);
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn local_normal_at(&self, point: Point, _hit: &Intersection) -> Normal {
+        let dist = point.x().powi(2) + point.z().powi(2);
+
+        if dist < self.maximum.powi(2) && point.y() >= self.maximum - EPSILON {
+            Normal::new(0.0, 1.0, 0.0)
+        } else if dist < self.minimum.powi(2) && point.y() <= self.minimum + EPSILON {
+            Normal::new(0.0, -1.0, 0.0)
+        } else {
+            let mut y = dist.sqrt();
+            if point.y() > 0.0 {
+                y = -y;
+            }
+            Normal::new(point.x(), y, point.z())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tup::Vector;
+
+    #[test]
+    fn intersect() {
+        let c = Cone::new(Matrix4x4::identity());
+        let cases = [
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 1.0, 1.0), 8.66025, 8.66025),
+            (Point::new(1.0, 1.0, -5.0), Vector::new(-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+
+        for (origin, direction, t0, t1) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+
+            assert_eq!(xs.xs.len(), 2);
+            assert!(abs_diff_eq!(xs.xs[0].t, *t0, epsilon=0.001));
+            assert!(abs_diff_eq!(xs.xs[1].t, *t1, epsilon=0.001));
+        }
+    }
+
+    #[test]
+    fn intersect_parallel() {
+        let c = Cone::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 0.0, -1.0),
+                         Vector::new(0.0, 1.0, 1.0).norm());
+        let xs = c.local_intersect(r);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert!(abs_diff_eq!(xs.xs[0].t, 0.35355, epsilon=0.0001));
+    }
+
+    #[test]
+    fn intersect_parallel_through_apex() {
+        let c = Cone::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0),
+                         Vector::new(0.0, 1.0, 1.0).norm());
+
+        assert_eq!(c.local_intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn bounds() {
+        let c = Cone::new(Matrix4x4::identity()).with_bounds(-5.0, 3.0);
+
+        assert_eq!(c.local_bounds().min, Point::new(-5.0, -5.0, -5.0));
+        assert_eq!(c.local_bounds().max, Point::new(5.0, 3.0, 5.0));
+        assert!(!Cone::new(Matrix4x4::identity()).bounds().is_finite());
+    }
+
+    #[test]
+    fn intersect_caps() {
+        let c = Cone::new(Matrix4x4::identity())
+            .with_bounds(-0.5, 0.5)
+            .with_closed(true);
+        let cases = [
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0), 0),
+            (Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -0.25), Vector::new(0.0, 1.0, 0.0), 4),
+        ];
+
+        for (origin, direction, count) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+            assert_eq!(xs.xs.len(), *count);
+        }
+    }
+
+    #[test]
+    fn normal() {
+        let c = Cone::new(Matrix4x4::identity());
+        let i = Intersection::new(0.0, &c);
+        let cases = [
+            (Point::new(0.0, 0.0, 0.0), Normal::new(0.0, 0.0, 0.0)),
+            (Point::new(1.0, 1.0, 1.0), Normal::new(1.0, -(2.0_f32.sqrt()), 1.0)),
+            (Point::new(-1.0, -1.0, 0.0), Normal::new(-1.0, 1.0, 0.0)),
+        ];
+
+        for (point, normal) in cases.iter() {
+            assert_eq!(c.local_normal_at(*point, &i), *normal);
+        }
+    }
+
+    #[test]
+    fn normal_caps() {
+        let c = Cone::new(Matrix4x4::identity())
+            .with_bounds(-1.0, 2.0)
+            .with_closed(true);
+        let i = Intersection::new(0.0, &c);
+
+        assert_eq!(c.local_normal_at(Point::new(0.5, 2.0, 0.5), &i), Normal::new(0.0, 1.0, 0.0));
+        assert_eq!(c.local_normal_at(Point::new(0.5, -1.0, 0.0), &i), Normal::new(0.0, -1.0, 0.0));
+    }
+}
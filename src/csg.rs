@@ -0,0 +1,270 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    // Whether a hit on one side is kept, given whether it is on the left
+    // shape and whether it lies inside the left and right shapes.
+    fn allows(self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOp::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOp::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+// Two shapes combined by a set operation. Like a group's, the children's
+// transforms are relative to the CSG and follow it when it moves.
+#[derive(Debug)]
+pub struct Csg {
+    transform: Transform,
+    material: Material,
+    operation: CsgOp,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+    // The children's transforms relative to the CSG, left then right.
+    locals: [Transform; 2],
+}
+
+impl Csg {
+    pub fn new(transform: Matrix4x4,
+               operation: CsgOp,
+               mut left: Box<dyn Shape>,
+               mut right: Box<dyn Shape>) -> Csg {
+        let transform = Transform::new(transform)
+            .expect("csg transform is not invertible");
+        let locals = [*left.transform(), *right.transform()];
+        left.set_transform(transform * locals[0]);
+        right.set_transform(transform * locals[1]);
+
+        Csg {
+            transform,
+            material: Material::default(),
+            operation,
+            left,
+            right,
+            locals,
+        }
+    }
+
+    pub fn operation(&self) -> CsgOp {
+        self.operation
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    // Walks the sorted hits, tracking whether each point is inside either
+    // child, and keeps those on the surface of the combined shape.
+    fn filter_intersections<'a>(&self, xs: Vec<Intersection<'a>>) -> Vec<Intersection<'a>> {
+        let mut in_left = false;
+        let mut in_right = false;
+
+        xs.into_iter()
+            .filter(|x| {
+                let left_hit = self.left.includes(x.object);
+                let allowed = self.operation.allows(left_hit, in_left, in_right);
+
+                if left_hit {
+                    in_left = !in_left;
+                } else {
+                    in_right = !in_right;
+                }
+
+                allowed
+            })
+            .collect()
+    }
+}
+
+impl Shape for Csg {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.left.set_transform(self.transform * self.locals[0]);
+        self.right.set_transform(self.transform * self.locals[1]);
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds().transform(self.transform.inverse())
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.left.bounds().merge(self.right.bounds())
+    }
+
+    fn intersect(&self, ray: Ray) -> Intersections<'_> {
+        let mut xs = self.left.intersect(ray).xs;
+        xs.extend(self.right.intersect(ray).xs);
+        xs.sort_by(|x, y| x.t.partial_cmp(&y.t).unwrap());
+
+        Intersections::new(self.filter_intersections(xs))
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        self.intersect(ray.transform(self.transform.matrix()))
+    }
+
+    fn local_normal_at(&self, _point: Point, _hit: &Intersection) -> Normal {
+        panic!("csg shapes have no normal; the hit is always on one of the children")
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.left.includes(other) || self.right.includes(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::sphere::Sphere;
+    use crate::transform::*;
+    use crate::tup::Vector;
+
+    fn csg(operation: CsgOp) -> Csg {
+        Csg::new(Matrix4x4::identity(), operation,
+                 Box::new(Sphere::unit()),
+                 Box::new(Cube::new(Matrix4x4::identity())))
+    }
+
+    #[test]
+    fn create() {
+        let c = csg(CsgOp::Union);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = c.left().intersect(r).hit().unwrap();
+
+        assert_eq!(c.operation(), CsgOp::Union);
+        assert!(c.includes(hit.object));
+        assert!(!c.right().includes(hit.object));
+    }
+
+    #[test]
+    fn rules() {
+        // Indexed by (left_hit, in_left, in_right), from (true, true, true)
+        // down to (false, false, false).
+        let cases = [
+            (CsgOp::Union, [false, true, false, true, false, false, true, true]),
+            (CsgOp::Intersection, [true, false, true, false, true, true, false, false]),
+            (CsgOp::Difference, [false, true, false, true, true, true, false, false]),
+        ];
+
+        for (op, expected) in cases.iter() {
+            for (i, allowed) in expected.iter().enumerate() {
+                let left_hit = i & 4 == 0;
+                let in_left = i & 2 == 0;
+                let in_right = i & 1 == 0;
+
+                assert_eq!(op.allows(left_hit, in_left, in_right), *allowed,
+                           "{:?} {} {} {}", op, left_hit, in_left, in_right);
+            }
+        }
+    }
+
+    #[test]
+    fn filter() {
+        let cases = [
+            (CsgOp::Union, [0, 3]),
+            (CsgOp::Intersection, [1, 2]),
+            (CsgOp::Difference, [0, 1]),
+        ];
+
+        for (op, expected) in cases.iter() {
+            let c = csg(*op);
+            let xs = vec![Intersection::new(1.0, c.left()),
+                          Intersection::new(2.0, c.right()),
+                          Intersection::new(3.0, c.left()),
+                          Intersection::new(4.0, c.right())];
+            let result = c.filter_intersections(xs.clone());
+
+            assert_eq!(result, vec![xs[expected[0]], xs[expected[1]]]);
+        }
+    }
+
+    #[test]
+    fn miss() {
+        let c = csg(CsgOp::Union);
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(c.intersect(r).xs.is_empty());
+    }
+
+    #[test]
+    fn hit() {
+        let c = Csg::new(Matrix4x4::identity(), CsgOp::Union,
+                         Box::new(Sphere::unit()),
+                         Box::new(Sphere::new(translation(0.0, 0.0, 0.5))));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).xs;
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[0].object, c.left());
+        assert_eq!(xs[1].t, 6.5);
+        assert_eq!(xs[1].object, c.right());
+    }
+
+    #[test]
+    fn drilled() {
+        // A cube with a hole bored through it along z, moved as a unit.
+        let hole = Sphere::new(scaling(0.5, 0.5, 2.0));
+        let c = Csg::new(translation(0.0, 0.0, 10.0), CsgOp::Difference,
+                         Box::new(Cube::new(Matrix4x4::identity())),
+                         Box::new(hole));
+
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(c.intersect(r).xs.is_empty());
+
+        let r = Ray::new(Point::new(0.75, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).xs;
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 9.0);
+        assert_eq!(xs[1].t, 11.0);
+
+        let r = Ray::new(Point::new(0.0, -5.0, 10.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = c.intersect(r).xs;
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[1].object, c.right());
+        let n = xs[1].object.normal_at(r.position(xs[1].t), &xs[1]);
+        assert_eq!(n, Normal::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn move_after_building() {
+        let mut c = Csg::new(translation(0.0, 0.0, 10.0), CsgOp::Difference,
+                             Box::new(Cube::new(Matrix4x4::identity())),
+                             Box::new(Sphere::new(scaling(0.5, 0.5, 2.0))));
+        c.set_transform(Transform::new(translation(0.0, 0.0, 20.0)).unwrap());
+
+        let r = Ray::new(Point::new(0.75, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).xs;
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 19.0);
+        assert_eq!(xs[1].t, 21.0);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::EPSILON;
+
+// The axis-aligned box from (-1, -1, -1) to (1, 1, 1) in object space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cube {
+    transform: Transform,
+    material: Material,
+}
+
+impl Cube {
+    pub fn new(transform: Matrix4x4) -> Cube {
+        Cube {
+            transform: Transform::new(transform)
+                .expect("cube transform is not invertible"),
+            material: Material::default(),
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Cube {
+        Cube {
+            material,
+            ..self
+        }
+    }
+}
+
+// Where a ray enters and leaves the slab between -1 and 1 along one axis.
+fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (tmin_numerator * f32::INFINITY, tmax_numerator * f32::INFINITY)
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let (xtmin, xtmax) = check_axis(ray.origin.x(), ray.direction.x());
+        let (ytmin, ytmax) = check_axis(ray.origin.y(), ray.direction.y());
+        let (ztmin, ztmax) = check_axis(ray.origin.z(), ray.direction.z());
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            Intersections::new(vec![])
+        } else {
+            Intersections::new(vec![Intersection::new(tmin, self),
+                                    Intersection::new(tmax, self)])
+        }
+    }
+
+    fn local_normal_at(&self, point: Point, _hit: &Intersection) -> Normal {
+        let ax = point.x().abs();
+        let ay = point.y().abs();
+        let az = point.z().abs();
+        let maxc = ax.max(ay).max(az);
+
+        if maxc == ax {
+            Normal::new(point.x(), 0.0, 0.0)
+        } else if maxc == ay {
+            Normal::new(0.0, point.y(), 0.0)
+        } else {
+            Normal::new(0.0, 0.0, point.z())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tup::Vector;
+    use crate::transform::*;
+
+    #[test]
+    fn intersect() {
+        let c = Cube::new(Matrix4x4::identity());
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0), 4.0, 6.0),
+            (Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0), 4.0, 6.0),
+            (Point::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), 4.0, 6.0),
+            (Point::new(0.5, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0), 4.0, 6.0),
+            (Point::new(0.5, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), 4.0, 6.0),
+            (Point::new(0.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+
+        for (origin, direction, t1, t2) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, *direction));
+
+            assert_eq!(xs.xs.len(), 2);
+            assert_eq!(xs.xs[0].t, *t1);
+            assert_eq!(xs.xs[1].t, *t2);
+        }
+    }
+
+    #[test]
+    fn intersect_miss() {
+        let c = Cube::new(Matrix4x4::identity());
+        let cases = [
+            (Point::new(-2.0, 0.0, 0.0), Vector::new(0.2673, 0.5345, 0.8018)),
+            (Point::new(0.0, -2.0, 0.0), Vector::new(0.8018, 0.2673, 0.5345)),
+            (Point::new(0.0, 0.0, -2.0), Vector::new(0.5345, 0.8018, 0.2673)),
+            (Point::new(2.0, 0.0, 2.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, 2.0, 2.0), Vector::new(0.0, -1.0, 0.0)),
+            (Point::new(2.0, 2.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+        ];
+
+        for (origin, direction) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, *direction));
+            assert_eq!(xs.xs.len(), 0);
+        }
+    }
+
+    #[test]
+    fn normal() {
+        let c = Cube::new(Matrix4x4::identity());
+        let i = Intersection::new(0.0, &c);
+        let cases = [
+            (Point::new(1.0, 0.5, -0.8), Normal::new(1.0, 0.0, 0.0)),
+            (Point::new(-1.0, -0.2, 0.9), Normal::new(-1.0, 0.0, 0.0)),
+            (Point::new(-0.4, 1.0, -0.1), Normal::new(0.0, 1.0, 0.0)),
+            (Point::new(0.3, -1.0, -0.7), Normal::new(0.0, -1.0, 0.0)),
+            (Point::new(-0.6, 0.3, 1.0), Normal::new(0.0, 0.0, 1.0)),
+            (Point::new(0.4, 0.4, -1.0), Normal::new(0.0, 0.0, -1.0)),
+            (Point::new(1.0, 1.0, 1.0), Normal::new(1.0, 0.0, 0.0)),
+            (Point::new(-1.0, -1.0, -1.0), Normal::new(-1.0, 0.0, 0.0)),
+        ];
+
+        for (point, normal) in cases.iter() {
+            assert_eq!(c.local_normal_at(*point, &i), *normal);
+        }
+    }
+
+    #[test]
+    fn transformed() {
+        let c = Cube::new(scaling(2.0, 1.0, 1.0).translate(0.0, 0.0, 5.0));
+        let i = Intersection::new(0.0, &c);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r);
+
+        assert_eq!(xs.xs.len(), 2);
+        assert_eq!(xs.xs[0].t, 4.0);
+        assert_eq!(xs.xs[1].t, 6.0);
+        assert_eq!(c.normal_at(Point::new(1.0, 0.0, 4.0), &i), Normal::new(0.0, 0.0, -1.0));
+        assert_eq!(c.normal_at(Point::new(2.0, 0.0, 5.0), &i), Normal::new(1.0, 0.0, 0.0));
+    }
+}
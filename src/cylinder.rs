@@ -0,0 +1,277 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::EPSILON;
+
+// A cylinder of radius 1 around the y axis in object space, truncated to
+// `minimum < y < maximum` and optionally capped at both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    transform: Transform,
+    material: Material,
+    minimum: f32,
+    maximum: f32,
+    closed: bool,
+}
+
+impl Cylinder {
+    pub fn new(transform: Matrix4x4) -> Cylinder {
+        Cylinder {
+            transform: Transform::new(transform)
+                .expect("cylinder transform is not invertible"),
+            material: Material::default(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Cylinder {
+        Cylinder {
+            material,
+            ..self
+        }
+    }
+
+    pub fn with_bounds(self, minimum: f32, maximum: f32) -> Cylinder {
+        Cylinder {
+            minimum,
+            maximum,
+            ..self
+        }
+    }
+
+    pub fn with_closed(self, closed: bool) -> Cylinder {
+        Cylinder {
+            closed,
+            ..self
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, ray: Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y().abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum].iter() {
+            let t = (y - ray.origin.y()) / ray.direction.y();
+            if check_cap(ray, t) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+// Whether the ray at `t` is within the unit radius of the y axis.
+fn check_cap(ray: Ray, t: f32) -> bool {
+    let x = ray.origin.x() + t * ray.direction.x();
+    let z = ray.origin.z() + t * ray.direction.z();
+
+    (x.powi(2) + z.powi(2)) <= 1.0 + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(Point::new(-1.0, self.minimum, -1.0),
+                    Point::new(1.0, self.maximum, 1.0))
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let mut xs = vec![];
+        let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
+
+        // A ray parallel to the y axis can only hit the caps.
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x() * ray.direction.x()
+                + 2.0 * ray.origin.z() * ray.direction.z();
+            let c = ray.origin.x().powi(2) + ray.origin.z().powi(2) - 1.0;
+
+            let disc = b.powi(2) - 4.0 * a * c;
+            if disc < 0.0 {
+                return Intersections::new(vec![]);
+            }
+
+            let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for t in [t0, t1].iter() {
+                let y = ray.origin.y() + t * ray.direction.y();
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(*t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn local_normal_at(&self, point: Point, _hit: &Intersection) -> Normal {
+        let dist = point.x().powi(2) + point.z().powi(2);
+
+        if dist < 1.0 && point.y() >= self.maximum - EPSILON {
+            Normal::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && point.y() <= self.minimum + EPSILON {
+            Normal::new(0.0, -1.0, 0.0)
+        } else {
+            Normal::new(point.x(), 0.0, point.z())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tup::Vector;
+    use crate::transform::*;
+
+    #[test]
+    fn intersect_miss() {
+        let c = Cylinder::new(Matrix4x4::identity());
+        let cases = [
+            (Point::new(1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(1.0, 1.0, 1.0)),
+        ];
+
+        for (origin, direction) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+            assert_eq!(xs.xs.len(), 0);
+        }
+    }
+
+    #[test]
+    fn intersect() {
+        let c = Cylinder::new(Matrix4x4::identity());
+        let cases = [
+            (Point::new(1.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Point::new(0.5, 0.0, -5.0), Vector::new(0.1, 1.0, 1.0), 6.80798, 7.08872),
+        ];
+
+        for (origin, direction, t0, t1) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+
+            assert_eq!(xs.xs.len(), 2);
+            assert!(abs_diff_eq!(xs.xs[0].t, *t0, epsilon=0.0001));
+            assert!(abs_diff_eq!(xs.xs[1].t, *t1, epsilon=0.0001));
+        }
+    }
+
+    #[test]
+    fn normal() {
+        let c = Cylinder::new(Matrix4x4::identity());
+        let i = Intersection::new(0.0, &c);
+        let cases = [
+            (Point::new(1.0, 0.0, 0.0), Normal::new(1.0, 0.0, 0.0)),
+            (Point::new(0.0, 5.0, -1.0), Normal::new(0.0, 0.0, -1.0)),
+            (Point::new(0.0, -2.0, 1.0), Normal::new(0.0, 0.0, 1.0)),
+            (Point::new(-1.0, 1.0, 0.0), Normal::new(-1.0, 0.0, 0.0)),
+        ];
+
+        for (point, normal) in cases.iter() {
+            assert_eq!(c.local_normal_at(*point, &i), *normal);
+        }
+    }
+
+    #[test]
+    fn default_bounds() {
+        let c = Cylinder::new(Matrix4x4::identity());
+
+        assert_eq!(c.minimum, f32::NEG_INFINITY);
+        assert_eq!(c.maximum, f32::INFINITY);
+        assert!(!c.closed);
+    }
+
+    #[test]
+    fn intersect_truncated() {
+        let c = Cylinder::new(Matrix4x4::identity()).with_bounds(1.0, 2.0);
+        let cases = [
+            (Point::new(0.0, 1.5, 0.0), Vector::new(0.1, 1.0, 0.0), 0),
+            (Point::new(0.0, 3.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0), 0),
+            (Point::new(0.0, 1.5, -2.0), Vector::new(0.0, 0.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+            assert_eq!(xs.xs.len(), *count);
+        }
+    }
+
+    #[test]
+    fn intersect_caps() {
+        let c = Cylinder::new(Matrix4x4::identity())
+            .with_bounds(1.0, 2.0)
+            .with_closed(true);
+        let cases = [
+            (Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0), 2),
+            (Point::new(0.0, 3.0, -2.0), Vector::new(0.0, -1.0, 2.0), 2),
+            (Point::new(0.0, 4.0, -2.0), Vector::new(0.0, -1.0, 1.0), 2),
+            (Point::new(0.0, 0.0, -2.0), Vector::new(0.0, 1.0, 2.0), 2),
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in cases.iter() {
+            let xs = c.local_intersect(Ray::new(*origin, direction.norm()));
+            assert_eq!(xs.xs.len(), *count);
+        }
+    }
+
+    #[test]
+    fn normal_caps() {
+        let c = Cylinder::new(Matrix4x4::identity())
+            .with_bounds(1.0, 2.0)
+            .with_closed(true);
+        let i = Intersection::new(0.0, &c);
+        let cases = [
+            (Point::new(0.0, 1.0, 0.0), Normal::new(0.0, -1.0, 0.0)),
+            (Point::new(0.5, 1.0, 0.0), Normal::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 1.0, 0.5), Normal::new(0.0, -1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.0), Normal::new(0.0, 1.0, 0.0)),
+            (Point::new(0.5, 2.0, 0.0), Normal::new(0.0, 1.0, 0.0)),
+            (Point::new(0.0, 2.0, 0.5), Normal::new(0.0, 1.0, 0.0)),
+        ];
+
+        for (point, normal) in cases.iter() {
+            assert_eq!(c.local_normal_at(*point, &i), *normal);
+        }
+    }
+
+    #[test]
+    fn transformed() {
+        let c = Cylinder::new(scaling(2.0, 1.0, 2.0))
+            .with_bounds(0.0, 1.0)
+            .with_closed(true);
+        let i = Intersection::new(0.0, &c);
+        let r = Ray::new(Point::new(0.0, 5.0, 0.0),
+                         Vector::new(0.0, -1.0, 0.0));
+        let xs = c.intersect(r);
+
+        assert_eq!(xs.xs.len(), 2);
+        assert_eq!(c.normal_at(Point::new(2.0, 0.5, 0.0), &i), Normal::new(1.0, 0.0, 0.0));
+        assert_eq!(c.normal_at(Point::new(1.0, 1.0, 0.0), &i), Normal::new(0.0, 1.0, 0.0));
+    }
+}
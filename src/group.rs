@@ -0,0 +1,289 @@
+use std::sync::OnceLock;
+
+use crate::bounds::Bounds;
+use crate::bvh::Bvh;
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+
+// A collection of shapes transformed as a unit. Children carry their full
+// world transform, made from the group's and the transform they were
+// added with, and are updated whenever the group's transform changes. A
+// BVH over the children is built on the first intersection and dropped
+// whenever they change.
+#[derive(Debug)]
+pub struct Group {
+    transform: Transform,
+    material: Material,
+    children: Vec<Box<dyn Shape>>,
+    // Each child's transform relative to the group.
+    locals: Vec<Transform>,
+    bvh: OnceLock<Bvh>,
+}
+
+impl Group {
+    pub fn new(transform: Matrix4x4) -> Group {
+        Group {
+            transform: Transform::new(transform)
+                .expect("group transform is not invertible"),
+            material: Material::default(),
+            children: vec![],
+            locals: vec![],
+            bvh: OnceLock::new(),
+        }
+    }
+
+    pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+        let local = *child.transform();
+        child.set_transform(self.transform * local);
+        self.children.push(child);
+        self.locals.push(local);
+        self.bvh = OnceLock::new();
+    }
+
+    pub fn with_child(mut self, child: impl Shape + 'static) -> Group {
+        self.add_child(Box::new(child));
+        self
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<Bounds> = self.children.iter().map(|c| c.bounds()).collect();
+            Bvh::new(&bounds)
+        })
+    }
+}
+
+impl Shape for Group {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+
+        for (child, local) in self.children.iter_mut().zip(&self.locals) {
+            child.set_transform(self.transform * *local);
+        }
+        self.bvh = OnceLock::new();
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds().transform(self.transform.inverse())
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.bvh().bounds()
+    }
+
+    // Children already carry the group's transform, so they are given the
+    // world space ray directly.
+    fn intersect(&self, ray: Ray) -> Intersections<'_> {
+        let mut xs = vec![];
+        self.bvh().traverse(&ray, |i| xs.extend(self.children[i].intersect(ray).xs));
+
+        Intersections::new(xs)
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        self.intersect(ray.transform(self.transform.matrix()))
+    }
+
+    fn local_normal_at(&self, _point: Point, _hit: &Intersection) -> Normal {
+        panic!("groups have no normal; the hit is always on one of the children")
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.children.iter().any(|c| c.includes(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::transform::*;
+    use crate::tup::Vector;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn empty() {
+        let g = Group::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert!(g.is_empty());
+        assert!(g.intersect(r).xs.is_empty());
+    }
+
+    #[test]
+    fn intersect() {
+        let g = Group::new(Matrix4x4::identity())
+            .with_child(Sphere::unit())
+            .with_child(Sphere::new(translation(0.0, 0.0, -3.0)))
+            .with_child(Sphere::new(translation(5.0, 0.0, 0.0)));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(r).xs;
+
+        let s1 = g.children()[0].as_ref();
+        let s2 = g.children()[1].as_ref();
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].object, s2);
+        assert_eq!(xs[1].object, s2);
+        assert_eq!(xs[2].object, s1);
+        assert_eq!(xs[3].object, s1);
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let g = Group::new(scaling(2.0, 2.0, 2.0))
+            .with_child(Sphere::new(translation(5.0, 0.0, 0.0)));
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(g.intersect(r).xs.len(), 2);
+    }
+
+    #[test]
+    fn nested() {
+        let inner = Group::new(scaling(2.0, 2.0, 2.0))
+            .with_child(Sphere::new(translation(5.0, 0.0, 0.0)));
+        let outer = Group::new(rotation_y(PI / 2.0)).with_child(inner);
+
+        let r = Ray::new(Point::new(-5.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(outer.intersect(r).xs.is_empty());
+
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = outer.intersect(r).xs;
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 8.0);
+        assert_eq!(xs[1].t, 12.0);
+    }
+
+    #[test]
+    fn bounds() {
+        let g = Group::new(translation(1.0, 0.0, 0.0))
+            .with_child(Sphere::new(translation(2.0, 5.0, -3.0).scale(2.0, 2.0, 2.0)))
+            .with_child(Sphere::new(translation(-4.0, 0.0, 0.0)));
+
+        assert_eq!(g.bounds().min, Point::new(-4.0, -1.0, -8.0));
+        assert_eq!(g.bounds().max, Point::new(7.0, 12.0, 1.0));
+        assert_eq!(g.local_bounds().min, Point::new(-5.0, -1.0, -8.0));
+    }
+
+    #[test]
+    fn many_children() {
+        let mut g = Group::new(Matrix4x4::identity());
+        for x in 0..20 {
+            for y in 0..20 {
+                let m = scaling(0.4, 0.4, 0.4).translate(x as f32, y as f32, 0.0);
+                g.add_child(Box::new(Sphere::new(m)));
+            }
+        }
+
+        let r = Ray::new(Point::new(7.0, 11.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(r).xs;
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].object, g.children()[7 * 20 + 11].as_ref());
+
+        let r = Ray::new(Point::new(-1.0, 3.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(g.intersect(r).xs.len(), 40);
+
+        let r = Ray::new(Point::new(7.5, 11.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(g.intersect(r).xs.is_empty());
+    }
+
+    #[test]
+    fn includes() {
+        let inner = Group::new(Matrix4x4::identity()).with_child(Sphere::unit());
+        let g = Group::new(Matrix4x4::identity()).with_child(inner);
+        let other = Sphere::unit();
+
+        let s = match g.intersect(Ray::new(Point::new(0.0, 0.0, -5.0),
+                                           Vector::new(0.0, 0.0, 1.0))).hit() {
+            Some(hit) => hit.object,
+            None => panic!("expected a hit"),
+        };
+        assert!(g.includes(s));
+        assert!(!g.includes(&other));
+        assert!(!g.includes(&g));
+    }
+
+    // A sphere inside a scaled group inside a rotated one. Either scale
+    // leaves the sphere on the negative z axis.
+    fn nested_sphere(scale: Matrix4x4) -> Group {
+        let inner = Group::new(scale)
+            .with_child(Sphere::new(translation(5.0, 0.0, 0.0)));
+        Group::new(rotation_y(PI / 2.0)).with_child(inner)
+    }
+
+    fn sphere_in(g: &Group) -> &dyn Shape {
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        g.intersect(r).hit().unwrap().object
+    }
+
+    #[test]
+    fn world_to_object() {
+        let g = nested_sphere(scaling(2.0, 2.0, 2.0));
+        let s = sphere_in(&g);
+
+        assert_eq!(s.world_to_object(Point::new(-2.0, 0.0, -10.0)), Point::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_to_world() {
+        let g = nested_sphere(scaling(1.0, 2.0, 3.0));
+        let s = sphere_in(&g);
+        let a = 3.0_f32.sqrt() / 3.0;
+
+        assert_eq!(s.normal_to_world(Normal::new(a, a, a)),
+                   Normal::new(2.0 / 7.0, 3.0 / 7.0, -6.0 / 7.0));
+    }
+
+    #[test]
+    fn normal_on_child() {
+        let inner = Group::new(scaling(1.0, 2.0, 3.0))
+            .with_child(Sphere::new(translation(5.0, 0.0, 0.0)));
+        let outer = Group::new(rotation_y(PI / 2.0)).with_child(inner);
+
+        let a = 3.0_f32.sqrt();
+        let r = Ray::new(Point::new(a, 2.0 / a, -20.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = outer.intersect(r);
+        let hit = xs.hit().unwrap();
+        let n = hit.object.normal_at(r.position(hit.t), &hit);
+
+        assert_eq!(n, Normal::new(2.0 / 7.0, 3.0 / 7.0, -6.0 / 7.0));
+    }
+
+    #[test]
+    fn move_after_adding_children() {
+        let mut outer = nested_sphere(scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -20.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(outer.intersect(r).xs[0].t, 8.0);
+
+        outer.set_transform(Transform::new(rotation_y(PI / 2.0).translate(0.0, 0.0, 5.0)).unwrap());
+        let xs = outer.intersect(r);
+        assert_eq!(xs.xs[0].t, 13.0);
+
+        let s = sphere_in(&outer);
+        assert_eq!(s.world_to_object(Point::new(-2.0, 0.0, -5.0)), Point::new(0.0, 0.0, -1.0));
+
+        let hit = xs.hit().unwrap();
+        assert_eq!(hit.object.normal_at(r.position(hit.t), &hit), Normal::new(0.0, 0.0, -1.0));
+        assert_eq!(outer.bounds().min, Point::new(-2.0, -2.0, -7.0));
+    }
+}
@@ -1,54 +1,175 @@
-use crate::sphere::Sphere;
+use crate::rays::Ray;
+use crate::shape::Shape;
+use crate::tup::{Point, Vector, Normal};
+use crate::EPSILON;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Intersection {
+pub struct Intersection<'a> {
     pub t: f32,
-    pub object: Sphere,
+    pub object: &'a dyn Shape,
+    pub uv: Option<(f32, f32)>,
 }
 
-impl Intersection {
-    pub fn new(t: f32, object: Sphere) -> Intersection {
-        Intersection{t, object}
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Intersection<'a> {
+        Intersection{t, object, uv: None}
     }
+
+    pub fn with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Intersection<'a> {
+        Intersection{t, object, uv: Some((u, v))}
+    }
+
+    // `xs` are all the intersections along `ray`, used to find the
+    // materials on either side of this one.
+    pub fn prepare_computations(&self, ray: Ray, xs: &Intersections) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.direction;
+        let mut normalv = self.object.normal_at(point, self);
+
+        let inside = normalv.dot(eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+
+        let (n1, n2) = xs.refractive_indices(self);
+
+        Computations {
+            t: self.t,
+            object: self.object,
+            point,
+            eyev,
+            normalv,
+            inside,
+            // Nudged off the surface so the point doesn't shadow itself,
+            // and below it for refracted rays to start from.
+            over_point: point + normalv.to_vector() * EPSILON,
+            under_point: point - normalv.to_vector() * EPSILON,
+            reflectv: ray.direction.reflect(normalv),
+            n1,
+            n2,
+        }
+    }
+}
+
+// Everything about a hit that shading needs, worked out once. The normal
+// always faces the eye; `inside` records whether it had to be flipped.
+// `n1` is the refractive index the ray is leaving and `n2` the one it is
+// entering.
+#[derive(Debug, Clone, Copy)]
+pub struct Computations<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    pub point: Point,
+    pub eyev: Vector,
+    pub normalv: Normal,
+    pub inside: bool,
+    pub over_point: Point,
+    pub under_point: Point,
+    pub reflectv: Vector,
+    pub n1: f32,
+    pub n2: f32,
 }
 
+impl Computations<'_> {
+    // Schlick's approximation of the Fresnel equations: the fraction of
+    // light reflected rather than refracted at the hit.
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.normalv.dot(self.eyev);
+
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+// Always sorted by increasing `t`.
 #[derive(Debug, Clone)]
-pub struct Intersections {
-    pub xs: Vec<Intersection>,
+pub struct Intersections<'a> {
+    pub xs: Vec<Intersection<'a>>,
 }
 
-impl Intersections {
-    pub fn new(xs: Vec<Intersection>) -> Intersections {
+impl<'a> Intersections<'a> {
+    // Non-finite `t` values, e.g. from a NaN vertex in a loaded mesh, are
+    // dropped rather than sorted.
+    pub fn new(mut xs: Vec<Intersection<'a>>) -> Intersections<'a> {
+        xs.retain(|x| x.t.is_finite());
+        xs.sort_by(|x, y| x.t.total_cmp(&y.t));
         Intersections{xs}
     }
 
-    pub fn hit(&self) -> Option<Intersection> {
+    pub fn hit(&self) -> Option<Intersection<'a>> {
         self.xs.iter()
-            .filter(|x| x.t > 0.0)
-            .min_by(|x, y| x.t.partial_cmp(&y.t).unwrap())
+            .find(|x| x.t > 0.0)
             .cloned()
     }
+
+    // The refractive indices of the materials on either side of `hit`:
+    // the one the ray is leaving and the one it is entering. Walks the
+    // intersections in order, tracking which objects the ray is inside.
+    pub fn refractive_indices(&self, hit: &Intersection) -> (f32, f32) {
+        let mut containers: Vec<&dyn Shape> = vec![];
+        let index = |containers: &[&dyn Shape]| {
+            containers.last().map_or(1.0, |o| o.material().refractive_index)
+        };
+
+        for x in self.xs.iter() {
+            let n1 = index(&containers);
+
+            match containers.iter().position(|o| *o == x.object) {
+                Some(i) => { containers.remove(i); }
+                None => containers.push(x.object),
+            }
+
+            if x == hit {
+                return (n1, index(&containers));
+            }
+        }
+
+        (1.0, 1.0)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::material::Material;
+    use crate::matrix::Matrix4x4;
+    use crate::plane::Plane;
     use crate::sphere::Sphere;
+    use crate::transform::*;
 
     #[test]
     fn intersection() {
         let s = Sphere::unit();
-        let i = Intersection::new(3.5, s);
+        let i = Intersection::new(3.5, &s);
 
         assert_eq!(i.t, 3.5);
-        assert_eq!(i.object, s);
+        assert_eq!(i.object, &s as &dyn Shape);
+        assert_eq!(i.uv, None);
+    }
+
+    #[test]
+    fn intersection_uv() {
+        let s = Sphere::unit();
+        let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+
+        assert_eq!(i.uv, Some((0.2, 0.4)));
     }
 
     #[test]
     fn intersections() {
         let s = Sphere::unit();
-        let i1 = Intersection::new(1.0, s);
-        let i2 = Intersection::new(2.0, s);
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
         let is = Intersections::new(vec![i1, i2]);
 
         assert_eq!(is.xs.len(), 2);
@@ -56,26 +177,75 @@ mod tests {
         assert_eq!(is.xs[1].t, 2.0);
     }
 
+    #[test]
+    fn sorted() {
+        let s = Sphere::unit();
+        let xs = Intersections::new(vec![Intersection::new(5.0, &s),
+                                         Intersection::new(-3.0, &s),
+                                         Intersection::new(2.0, &s)]);
+        let ts: Vec<f32> = xs.xs.iter().map(|x| x.t).collect();
+
+        assert_eq!(ts, vec![-3.0, 2.0, 5.0]);
+    }
+
+    fn glass_sphere(transform: Matrix4x4, refractive_index: f32) -> Sphere {
+        Sphere::new(transform).with_material(Material {
+            transparency: 1.0,
+            refractive_index,
+            ..Material::default()
+        })
+    }
+
+    #[test]
+    fn refractive_indices() {
+        let a = glass_sphere(scaling(2.0, 2.0, 2.0), 1.5);
+        let b = glass_sphere(translation(0.0, 0.0, -0.25), 2.0);
+        let c = glass_sphere(translation(0.0, 0.0, 0.25), 2.5);
+        let xs = Intersections::new(vec![Intersection::new(2.0, &a),
+                                         Intersection::new(2.75, &b),
+                                         Intersection::new(3.25, &c),
+                                         Intersection::new(4.75, &b),
+                                         Intersection::new(5.25, &c),
+                                         Intersection::new(6.0, &a)]);
+        let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.5),
+                        (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+
+        for (x, n) in xs.xs.iter().zip(expected.iter()) {
+            assert_eq!(xs.refractive_indices(x), *n);
+        }
+    }
+
+    #[test]
+    fn non_finite() {
+        let s = Sphere::unit();
+        let xs = Intersections::new(vec![Intersection::new(f32::NAN, &s),
+                                         Intersection::new(2.0, &s),
+                                         Intersection::new(f32::INFINITY, &s)]);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert_eq!(xs.xs[0].t, 2.0);
+    }
+
     #[test]
     fn hit() {
         let s = Sphere::unit();
-        let i1 = Intersection::new(1.0, s);
-        let i2 = Intersection::new(2.0, s);
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
         let xs = Intersections::new(vec![i1, i2]);
 
         assert_eq!(xs.hit().unwrap(), i1);
 
-        let i1 = Intersection::new(-1.0, s);
-        let i2 = Intersection::new(2.0, s);
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
         let xs = Intersections::new(vec![i1, i2]);
 
         assert_eq!(xs.hit().unwrap(), i2);
 
 
-        let i1 = Intersection::new(5.0, s);
-        let i2 = Intersection::new(7.0, s);
-        let i3 = Intersection::new(-3.0, s);
-        let i4 = Intersection::new(2.0, s);
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
         let xs = Intersections::new(vec![i1, i2, i3, i4]);
 
         assert_eq!(xs.hit().unwrap(), i4);
@@ -85,11 +255,94 @@ mod tests {
     #[should_panic]
     fn hit_none() {
         let s = Sphere::unit();
-        let i1 = Intersection::new(-1.0, s);
-        let i2 = Intersection::new(-2.0, s);
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(-2.0, &s);
         let xs = Intersections::new(vec![i1, i2]);
 
         assert_eq!(xs.hit().unwrap(), i2);
     }
 
+    #[test]
+    fn prepare_computations() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Sphere::unit();
+        let i = Intersection::new(4.0, &s);
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.t, i.t);
+        assert_eq!(comps.object, i.object);
+        assert_eq!(comps.point, Point::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Normal::new(0.0, 0.0, -1.0));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn prepare_computations_inside() {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let s = Sphere::unit();
+        let i = Intersection::new(1.0, &s);
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.point, Point::new(0.0, 0.0, 1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Normal::new(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+    }
+
+    #[test]
+    fn over_and_under_point() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s = glass_sphere(translation(0.0, 0.0, 1.0), 1.5);
+        let i = Intersection::new(5.0, &s);
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert!(comps.over_point.z() < -EPSILON / 2.0);
+        assert!(comps.point.z() > comps.over_point.z());
+        assert!(comps.under_point.z() > EPSILON / 2.0);
+        assert!(comps.point.z() < comps.under_point.z());
+    }
+
+    #[test]
+    fn prepare_computations_reflectv() {
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 1.0, -1.0), Vector::new(0.0, -a, a));
+        let p = Plane::new(Matrix4x4::identity());
+        let i = Intersection::new(2.0_f32.sqrt(), &p);
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.reflectv, Vector::new(0.0, a, a));
+    }
+
+    #[test]
+    fn schlick_total_internal_reflection() {
+        let a = 2.0_f32.sqrt() / 2.0;
+        let s = glass_sphere(Matrix4x4::identity(), 1.5);
+        let r = Ray::new(Point::new(0.0, 0.0, a), Vector::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(-a, &s),
+                                         Intersection::new(a, &s)]);
+
+        assert_eq!(xs.xs[1].prepare_computations(r, &xs).schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_perpendicular() {
+        let s = glass_sphere(Matrix4x4::identity(), 1.5);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(-1.0, &s),
+                                         Intersection::new(1.0, &s)]);
+        let reflectance = xs.xs[1].prepare_computations(r, &xs).schlick();
+
+        assert!((reflectance - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn schlick_small_angle() {
+        let s = glass_sphere(Matrix4x4::identity(), 1.5);
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &s)]);
+        let reflectance = xs.xs[0].prepare_computations(r, &xs).schlick();
+
+        assert!((reflectance - 0.48873).abs() < 0.001);
+    }
 }
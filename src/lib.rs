@@ -3,11 +3,34 @@
 #[macro_use]
 extern crate approx;
 
+pub const EPSILON: f32 = 0.0001;
+
 pub mod tup;
 pub mod color;
 pub mod canvas;
 pub mod matrix;
 pub mod transform;
+pub mod bounds;
+pub mod bvh;
 pub mod rays;
+pub mod shape;
 pub mod sphere;
+pub mod plane;
+pub mod cube;
+pub mod cylinder;
+pub mod cone;
+pub mod group;
+pub mod csg;
+pub mod triangle;
+pub mod smooth_triangle;
 pub mod intersection;
+pub mod material;
+pub mod pattern;
+pub mod light;
+pub mod world;
+pub mod camera;
+pub mod mesh;
+pub mod obj;
+pub mod mtl;
+pub mod ply;
+pub mod stl;
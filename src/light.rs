@@ -0,0 +1,175 @@
+use crate::color::{self, Color};
+use crate::tup::{Point, Vector, Normal};
+use crate::material::Material;
+use crate::shape::Shape;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Point,
+    pub intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point, intensity: Color) -> PointLight {
+        PointLight{position, intensity}
+    }
+}
+
+// `object` is the shape being lit, which patterns are evaluated against.
+pub fn lighting(material: &Material,
+                object: &dyn Shape,
+                light: &PointLight,
+                point: Point,
+                eyev: Vector,
+                normalv: Normal,
+                in_shadow: bool) -> Color {
+    let color = match &material.pattern {
+        Some(pattern) => pattern.pattern_at_shape(object, point),
+        None => material.color,
+    };
+    let effective_color = color * light.intensity;
+    let lightv = (light.position - point).norm();
+    let ambient = effective_color * material.ambient;
+
+    if in_shadow {
+        return ambient;
+    }
+
+    // A negative dot product means the light is on the other side of the
+    // surface, so only ambient light reaches the point.
+    let light_dot_normal = normalv.dot(lightv);
+    if light_dot_normal < 0.0 {
+        return ambient;
+    }
+
+    let diffuse = effective_color * material.diffuse * light_dot_normal;
+
+    let reflectv = (-lightv).reflect(normalv);
+    let reflect_dot_eye = reflectv.dot(eyev);
+
+    let specular = if reflect_dot_eye <= 0.0 {
+        color::BLACK
+    } else {
+        let factor = reflect_dot_eye.powf(material.shininess);
+        light.intensity * material.specular * factor
+    };
+
+    ambient + diffuse + specular
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix4x4;
+    use crate::pattern::Stripe;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn point_light() {
+        let intensity = Color::new(1.0, 1.0, 1.0);
+        let position = Point::new(0.0, 0.0, 0.0);
+        let light = PointLight::new(position, intensity);
+
+        assert_eq!(light.position, position);
+        assert_eq!(light.intensity, intensity);
+    }
+
+    #[test]
+    fn eye_between_light_and_surface() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, false),
+                   Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn eye_offset_45() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let eyev = Vector::new(0.0, a, -a);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, false),
+                   Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn light_offset_45() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, false),
+                   Color::new(0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn eye_in_reflection_path() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let eyev = Vector::new(0.0, -a, -a);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, false),
+                   Color::new(1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn light_behind_surface() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, 10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, false),
+                   Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn surface_in_shadow() {
+        let m = Material::default();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(lighting(&m, &Sphere::unit(), &light, position, eyev, normalv, true),
+                   Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn pattern() {
+        let m = Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        }.with_pattern(Stripe::new(color::WHITE, color::BLACK, Matrix4x4::identity()));
+        let s = Sphere::unit();
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Normal::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
+
+        assert_eq!(lighting(&m, &s, &light, Point::new(0.9, 0.0, 0.0), eyev, normalv, false),
+                   color::WHITE);
+        assert_eq!(lighting(&m, &s, &light, Point::new(1.1, 0.0, 0.0), eyev, normalv, false),
+                   color::BLACK);
+    }
+}
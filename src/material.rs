@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::color::{self, Color};
+use crate::pattern::Pattern;
+
+// `pattern`, when set, replaces the flat `color`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub color: Color,
+    pub pattern: Option<Arc<dyn Pattern>>,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+}
+
+impl Material {
+    pub fn with_pattern(self, pattern: impl Pattern + 'static) -> Material {
+        Material {
+            pattern: Some(Arc::new(pattern)),
+            ..self
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            color: color::WHITE,
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let m = Material::default();
+
+        assert_eq!(m.color, Color::new(1.0, 1.0, 1.0));
+        assert!(m.pattern.is_none());
+        assert_eq!(m.ambient, 0.1);
+        assert_eq!(m.diffuse, 0.9);
+        assert_eq!(m.specular, 0.9);
+        assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+    }
+}
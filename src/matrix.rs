@@ -1,7 +1,7 @@
 use std::cmp::PartialEq;
 use std::ops::Mul;
 
-use crate::tup::{Tup, Point, Vector};
+use crate::tup::{Tup, Point, Vector, Normal};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Matrix4x4 {
@@ -19,6 +19,7 @@ pub struct Matrix2x2 {
 }
 
 impl Matrix4x4 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(e11: f32, e12: f32, e13: f32, e14:f32,
                e21: f32, e22: f32, e23: f32, e24:f32,
                e31: f32, e32: f32, e33: f32, e34:f32,
@@ -57,9 +58,9 @@ impl Matrix4x4 {
     pub fn transpose(&self) -> Matrix4x4 {
         let mut elem = [[0.0f32; 4]; 4];
 
-        for i in 0..4 {
-            for j in 0..4 {
-                elem[j][i] = self.elem[i][j];
+        for (i, row) in self.elem.iter().enumerate() {
+            for (j, e) in row.iter().enumerate() {
+                elem[j][i] = *e;
             }
         }
 
@@ -107,31 +108,121 @@ impl Matrix4x4 {
         m
     }
 
+    // The 2x2 determinants of the top two and bottom two rows, shared by
+    // the closed-form determinant and adjugate below.
+    fn sub_determinants(&self) -> ([f32; 6], [f32; 6]) {
+        let m = &self.elem;
+
+        let s = [m[0][0] * m[1][1] - m[1][0] * m[0][1],
+                 m[0][0] * m[1][2] - m[1][0] * m[0][2],
+                 m[0][0] * m[1][3] - m[1][0] * m[0][3],
+                 m[0][1] * m[1][2] - m[1][1] * m[0][2],
+                 m[0][1] * m[1][3] - m[1][1] * m[0][3],
+                 m[0][2] * m[1][3] - m[1][2] * m[0][3]];
+
+        let c = [m[2][0] * m[3][1] - m[3][0] * m[2][1],
+                 m[2][0] * m[3][2] - m[3][0] * m[2][2],
+                 m[2][0] * m[3][3] - m[3][0] * m[2][3],
+                 m[2][1] * m[3][2] - m[3][1] * m[2][2],
+                 m[2][1] * m[3][3] - m[3][1] * m[2][3],
+                 m[2][2] * m[3][3] - m[3][2] * m[2][3]];
+
+        (s, c)
+    }
+
+    fn determinant_from(s: &[f32; 6], c: &[f32; 6]) -> f32 {
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
     pub fn determinant(self) -> f32 {
-        (0..4).map(|i| self.elem[0][i] * self.cofactor(0, i))
-            .fold(0.0, |acc, x| acc + x)
+        let (s, c) = self.sub_determinants();
+        Matrix4x4::determinant_from(&s, &c)
+    }
+
+    pub fn is_affine(&self) -> bool {
+        self.elem[3] == [0.0, 0.0, 0.0, 1.0]
     }
 
     pub fn inverse(&self) -> Option<Matrix4x4> {
-        let det = self.determinant();
+        if self.is_affine() {
+            return self.affine_inverse();
+        }
+
+        let (s, c) = self.sub_determinants();
+        let det = Matrix4x4::determinant_from(&s, &c);
 
         if det == 0.0 {
             return None;
         }
 
+        let m = &self.elem;
+        let inv = 1.0 / det;
+
+        Some(Matrix4x4::new(
+            ( m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3]) * inv,
+            (-m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3]) * inv,
+            ( m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3]) * inv,
+            (-m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3]) * inv,
+
+            (-m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1]) * inv,
+            ( m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1]) * inv,
+            (-m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1]) * inv,
+            ( m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1]) * inv,
+
+            ( m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0]) * inv,
+            (-m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0]) * inv,
+            ( m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0]) * inv,
+            (-m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0]) * inv,
+
+            (-m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0]) * inv,
+            ( m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0]) * inv,
+            (-m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0]) * inv,
+            ( m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0]) * inv))
+    }
+
+    // For a bottom row of (0, 0, 0, 1) the inverse is the inverse of the
+    // upper-left 3x3 block, with the translation run back through it.
+    fn affine_inverse(&self) -> Option<Matrix4x4> {
+        let m = &self.elem;
+
+        let c00 = m[1][1] * m[2][2] - m[1][2] * m[2][1];
+        let c01 = m[1][2] * m[2][0] - m[1][0] * m[2][2];
+        let c02 = m[1][0] * m[2][1] - m[1][1] * m[2][0];
+
+        let det = m[0][0] * c00 + m[0][1] * c01 + m[0][2] * c02;
+
+        if det == 0.0 {
+            return None;
+        }
+
+        let inv = 1.0 / det;
+
+        let a = [[c00 * inv,
+                  (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv,
+                  (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv],
+                 [c01 * inv,
+                  (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv,
+                  (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv],
+                 [c02 * inv,
+                  (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv,
+                  (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv]];
+
+        let t = [m[0][3], m[1][3], m[2][3]];
         let mut elem = [[0.0_f32; 4]; 4];
 
-        for row in 0..4 {
-            for col in 0..4 {
-                elem[col][row] = self.cofactor(row, col) / det;
-            }
+        for row in 0..3 {
+            elem[row][..3].copy_from_slice(&a[row]);
+            elem[row][3] = -(a[row][0] * t[0] + a[row][1] * t[1] + a[row][2] * t[2]);
         }
+        elem[3][3] = 1.0;
+
         Some(Matrix4x4{elem})
     }
 
 }
 
 impl Matrix3x3 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(e11: f32, e12: f32, e13: f32,
                e21: f32, e22: f32, e23: f32,
                e31: f32, e32: f32, e33: f32)
@@ -256,6 +347,16 @@ impl Mul<Vector> for Matrix4x4 {
     }
 }
 
+// Expects the inverse-transpose of an object's transform, not the transform
+// itself. The result is renormalized.
+impl Mul<Normal> for Matrix4x4 {
+    type Output = Normal;
+
+    fn mul(self, rhs: Normal) -> Normal {
+        Normal::from_tup(self * rhs.to_tup()).norm()
+    }
+}
+
 impl PartialEq for Matrix4x4 {
     fn eq(&self, rhs: &Matrix4x4) -> bool {
         for r in 0..4 {
@@ -522,4 +623,40 @@ mod tests {
         assert_ne!(b.inverse(), None);
         assert_eq!(c * b.inverse().unwrap(), a);
     }
+
+    #[test]
+    fn inverse_affine() {
+        let a = Matrix4x4::new(2.0, 0.5, 0.0, 3.0,
+                               0.0, 1.0, -4.0, -2.0,
+                               1.5, 0.0, 3.0, 7.0,
+                               0.0, 0.0, 0.0, 1.0);
+        assert!(a.is_affine());
+        assert_eq!(a * a.inverse().unwrap(), Matrix4x4::identity());
+        assert_eq!(a.inverse().unwrap() * a, Matrix4x4::identity());
+
+        let b = Matrix4x4::new(1.0, 2.0, 3.0, 4.0,
+                               2.0, 4.0, 6.0, 5.0,
+                               0.0, 1.0, 1.0, 6.0,
+                               0.0, 0.0, 0.0, 1.0);
+        assert_eq!(b.determinant(), 0.0);
+        assert_eq!(b.inverse(), None);
+    }
+
+    #[test]
+    fn inverse_cofactors() {
+        let a = Matrix4x4::new(-5.0, 2.0, 6.0, -8.0,
+                               1.0, -5.0, 1.0, 8.0,
+                               7.0, 7.0, -6.0, -7.0,
+                               1.0, -3.0, 7.0, 4.0);
+        let b = a.inverse().unwrap();
+        let det = a.determinant();
+
+        assert_eq!(det, 532.0);
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!(abs_diff_eq!(b.elem[col][row], a.cofactor(row, col) / det,
+                                     epsilon=0.00001));
+            }
+        }
+    }
 }
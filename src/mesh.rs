@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::group::Group;
+use crate::matrix::Matrix4x4;
+use crate::shape::Shape;
+
+pub const DEFAULT_GROUP: &str = "default";
+
+// Triangles loaded from a model file, in the named groups the file
+// declared them in. `warnings` lists problems the loader recovered from,
+// such as a missing material.
+#[derive(Debug, Default)]
+pub struct Mesh {
+    pub groups: Vec<MeshGroup>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MeshGroup {
+    pub name: String,
+    pub triangles: Vec<Box<dyn Shape>>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(|g| g.triangles.len()).sum()
+    }
+
+    pub fn group(&self, name: &str) -> Option<&MeshGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    pub fn into_shapes(self) -> Vec<Box<dyn Shape>> {
+        self.groups.into_iter()
+            .flat_map(|g| g.triangles)
+            .collect()
+    }
+
+    // One child group per mesh group, so the model can be placed in a
+    // scene as a single shape.
+    pub fn into_group(self, transform: Matrix4x4) -> Group {
+        let mut group = Group::new(transform);
+
+        for g in self.groups {
+            let mut child = Group::new(Matrix4x4::identity());
+            for t in g.triangles {
+                child.add_child(t);
+            }
+            group.add_child(Box::new(child));
+        }
+
+        group
+    }
+}
+
+impl MeshGroup {
+    pub fn new(name: &str) -> MeshGroup {
+        MeshGroup {
+            name: name.to_string(),
+            triangles: vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    Format(String),
+}
+
+impl MeshError {
+    pub fn parse(line: usize, message: impl Into<String>) -> MeshError {
+        MeshError::Parse { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::Io(e) => write!(f, "{}", e),
+            MeshError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            MeshError::Format(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for MeshError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MeshError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    fn from(e: io::Error) -> MeshError {
+        MeshError::Io(e)
+    }
+}
+
+// The message of a load that is expected to fail, for the format tests.
+#[cfg(test)]
+pub(crate) fn parse_error(result: Result<Mesh, MeshError>) -> String {
+    match result {
+        Err(e) => e.to_string(),
+        Ok(_) => panic!("expected an error"),
+    }
+}
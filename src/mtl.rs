@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::mesh::MeshError;
+
+// A material as declared by a `newmtl` block. Entries the file leaves out
+// fall back to `Material::default()` when converted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    pub ambient: Option<Color>,
+    pub diffuse: Option<Color>,
+    pub specular: Option<Color>,
+    pub shininess: Option<f32>,
+    pub dissolve: Option<f32>,
+    pub optical_density: Option<f32>,
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+    fn new(name: &str) -> MtlMaterial {
+        MtlMaterial {
+            name: name.to_string(),
+            ..MtlMaterial::default()
+        }
+    }
+
+    // `Kd` becomes the surface color. raytra's ambient and specular terms
+    // are scalar weights, so `Ka` and `Ks` are averaged down to one. `Ka`
+    // also scales the default ambient rather than replacing it: exporters
+    // commonly write `Ka 1 1 1`, which as a weight would wash the model out.
+    // The dissolve `d` is opacity, the complement of transparency, and `Ni`
+    // is the refractive index. `diffuse_map` is not applied, as materials
+    // have no image textures; the OBJ loader reports it as a warning.
+    pub fn to_material(&self) -> Material {
+        let mut m = Material::default();
+
+        if let Some(kd) = self.diffuse {
+            m.color = kd;
+        }
+        if let Some(ka) = self.ambient {
+            m.ambient *= average(ka);
+        }
+        if let Some(ks) = self.specular {
+            m.specular = average(ks);
+        }
+        if let Some(ns) = self.shininess {
+            m.shininess = ns;
+        }
+        if let Some(d) = self.dissolve {
+            m.transparency = 1.0 - d;
+        }
+        if let Some(ni) = self.optical_density {
+            m.refractive_index = ni;
+        }
+
+        m
+    }
+}
+
+fn average(c: Color) -> f32 {
+    (c.red() + c.green() + c.blue()) / 3.0
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<HashMap<String, MtlMaterial>, MeshError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    parse(&fs::read_to_string(path)?, dir)
+}
+
+// Texture paths are resolved relative to `dir`.
+pub fn parse(source: &str, dir: &Path) -> Result<HashMap<String, MtlMaterial>, MeshError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let text = match text.find('#') {
+            Some(i) => &text[..i],
+            None => text,
+        };
+        let fields: Vec<&str> = text.split_whitespace().collect();
+
+        let keyword = match fields.first() {
+            Some(k) => *k,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            if fields.len() < 2 {
+                return Err(MeshError::parse(line, "newmtl needs a name"));
+            }
+            if let Some(m) = current.take() {
+                materials.insert(m.name.clone(), m);
+            }
+            current = Some(MtlMaterial::new(&fields[1..].join(" ")));
+            continue;
+        }
+
+        let m = match current.as_mut() {
+            Some(m) => m,
+            // Anything before the first newmtl has nothing to apply to.
+            None => continue,
+        };
+
+        let args = &fields[1..];
+        match keyword {
+            "Ka" => m.ambient = Some(parse_color(line, args, keyword)?),
+            "Kd" => m.diffuse = Some(parse_color(line, args, keyword)?),
+            "Ks" => m.specular = Some(parse_color(line, args, keyword)?),
+            "Ns" => m.shininess = Some(parse_float(line, args, keyword)?),
+            "d" => m.dissolve = Some(parse_float(line, args, keyword)?),
+            "Tr" => m.dissolve = Some(1.0 - parse_float(line, args, keyword)?),
+            "Ni" => m.optical_density = Some(parse_float(line, args, keyword)?),
+            "map_Kd" => {
+                // Options such as `-s 1 1 1` come before the file name.
+                let file = args.last()
+                    .ok_or_else(|| MeshError::parse(line, "map_Kd needs a file name"))?;
+                m.diffuse_map = Some(dir.join(file));
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(m) = current {
+        materials.insert(m.name.clone(), m);
+    }
+
+    Ok(materials)
+}
+
+fn parse_float(line: usize, args: &[&str], keyword: &str) -> Result<f32, MeshError> {
+    match args {
+        [value] => value.parse::<f32>()
+            .map_err(|_| MeshError::parse(line, format!("invalid number '{}' in {}", value, keyword))),
+        _ => Err(MeshError::parse(line, format!(
+            "{} needs 1 value, found {}", keyword, args.len()))),
+    }
+}
+
+fn parse_color(line: usize, args: &[&str], keyword: &str) -> Result<Color, MeshError> {
+    let values = args.iter()
+        .map(|a| a.parse::<f32>()
+             .map_err(|_| MeshError::parse(line, format!("invalid number '{}' in {}", a, keyword))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A single value is shorthand for a grey.
+    match values[..] {
+        [v] => Ok(Color::new(v, v, v)),
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        _ => Err(MeshError::parse(line, format!(
+            "{} needs 1 or 3 values, found {}", keyword, values.len()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_materials() {
+        let source = "# two materials\n\
+                      newmtl red\n\
+                      Ka 0.3 0.0 0.0\n\
+                      Kd 1.0 0.0 0.0\n\
+                      Ks 0.5 0.5 0.2\n\
+                      Ns 96.0\n\
+                      d 0.5\n\
+                      Ni 1.5\n\
+                      map_Kd -s 1 1 1 textures/red.ppm\n\
+                      \n\
+                      newmtl plain\n\
+                      Tr 0.25\n";
+        let materials = parse(source, Path::new("models")).unwrap();
+
+        assert_eq!(materials.len(), 2);
+
+        let red = &materials["red"];
+        assert_eq!(red.ambient, Some(Color::new(0.3, 0.0, 0.0)));
+        assert_eq!(red.diffuse, Some(Color::new(1.0, 0.0, 0.0)));
+        assert_eq!(red.specular, Some(Color::new(0.5, 0.5, 0.2)));
+        assert_eq!(red.shininess, Some(96.0));
+        assert_eq!(red.dissolve, Some(0.5));
+        assert_eq!(red.optical_density, Some(1.5));
+        assert_eq!(red.diffuse_map, Some(Path::new("models").join("textures/red.ppm")));
+
+        let plain = &materials["plain"];
+        assert_eq!(plain.diffuse, None);
+        assert_eq!(plain.dissolve, Some(0.75));
+    }
+
+    #[test]
+    fn to_material() {
+        let m = MtlMaterial {
+            name: String::from("red"),
+            ambient: Some(Color::new(1.0, 1.0, 1.0)),
+            diffuse: Some(Color::new(1.0, 0.0, 0.0)),
+            specular: Some(Color::new(0.5, 0.5, 0.2)),
+            shininess: Some(96.0),
+            dissolve: Some(0.25),
+            optical_density: Some(1.5),
+            ..MtlMaterial::default()
+        };
+
+        assert_eq!(m.to_material(), Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.4,
+            shininess: 96.0,
+            transparency: 0.75,
+            refractive_index: 1.5,
+            ..Material::default()
+        });
+
+        let dim = MtlMaterial {
+            ambient: Some(Color::new(0.5, 0.2, 0.8)),
+            ..MtlMaterial::new("dim")
+        };
+        assert_eq!(dim.to_material().ambient, 0.05);
+
+        assert_eq!(MtlMaterial::new("empty").to_material(), Material::default());
+    }
+
+    #[test]
+    fn malformed() {
+        match parse("newmtl a\nKd 1 0\n", Path::new("")) {
+            Err(MeshError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+
+        match parse("newmtl a\nNs shiny\n", Path::new("")) {
+            Err(MeshError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
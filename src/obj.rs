@@ -0,0 +1,527 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+
+use crate::material::Material;
+use crate::mesh::{Mesh, MeshGroup, MeshError, DEFAULT_GROUP};
+use crate::mtl;
+use crate::shape::Shape;
+use crate::smooth_triangle::SmoothTriangle;
+use crate::triangle::Triangle;
+use crate::tup::{Point, Normal};
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh, MeshError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    parse(&fs::read_to_string(path)?, dir)
+}
+
+// Unrecognized record types are skipped; malformed records of a known type
+// are reported with their line number. Material libraries are resolved
+// relative to `dir`.
+pub fn parse(source: &str, dir: &Path) -> Result<Mesh, MeshError> {
+    let mut parser = Parser::new(dir);
+
+    for (i, line) in source.lines().enumerate() {
+        parser.parse_line(i + 1, line)?;
+    }
+
+    Ok(parser.finish())
+}
+
+struct FaceVertex {
+    vertex: usize,
+    normal: Option<usize>,
+}
+
+struct Parser {
+    vertices: Vec<Point>,
+    normals: Vec<Normal>,
+    texcoords: Vec<(f32, f32)>,
+    groups: Vec<MeshGroup>,
+    current: usize,
+    dir: PathBuf,
+    materials: HashMap<String, Material>,
+    material: Material,
+    warnings: Vec<String>,
+}
+
+impl Parser {
+    fn new(dir: &Path) -> Parser {
+        Parser {
+            vertices: vec![],
+            normals: vec![],
+            texcoords: vec![],
+            groups: vec![MeshGroup::new(DEFAULT_GROUP)],
+            current: 0,
+            dir: dir.to_path_buf(),
+            materials: HashMap::new(),
+            material: Material::default(),
+            warnings: vec![],
+        }
+    }
+
+    fn parse_line(&mut self, line: usize, text: &str) -> Result<(), MeshError> {
+        let text = match text.find('#') {
+            Some(i) => &text[..i],
+            None => text,
+        };
+        let mut fields = text.split_whitespace();
+
+        match fields.next() {
+            Some("v") => {
+                let xyz = parse_floats(line, fields, 3, 4, "vertex")?;
+                self.vertices.push(Point::new(xyz[0], xyz[1], xyz[2]));
+            }
+            Some("vn") => {
+                let xyz = parse_floats(line, fields, 3, 3, "vertex normal")?;
+                self.normals.push(Normal::new(xyz[0], xyz[1], xyz[2]));
+            }
+            Some("vt") => {
+                let uv = parse_floats(line, fields, 1, 3, "texture coordinate")?;
+                self.texcoords.push((uv[0], uv.get(1).cloned().unwrap_or(0.0)));
+            }
+            Some("f") => self.parse_face(line, fields)?,
+            Some("g") | Some("o") => {
+                let name = fields.collect::<Vec<_>>().join(" ");
+                let name = if name.is_empty() { DEFAULT_GROUP.to_string() } else { name };
+                self.select_group(&name);
+            }
+            Some("mtllib") => {
+                for file in fields {
+                    self.load_materials(line, file);
+                }
+            }
+            // A missing material shouldn't cost the whole model, so faces
+            // fall back to the default material instead.
+            Some("usemtl") => {
+                let name = fields.collect::<Vec<_>>().join(" ");
+                self.material = match self.materials.get(&name) {
+                    Some(m) => m.clone(),
+                    None => {
+                        self.warnings.push(format!("line {}: unknown material '{}'", line, name));
+                        Material::default()
+                    }
+                };
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn load_materials(&mut self, line: usize, file: &str) {
+        match mtl::load(self.dir.join(file)) {
+            Ok(materials) => {
+                for (name, m) in materials {
+                    if let Some(map) = &m.diffuse_map {
+                        self.warnings.push(format!(
+                            "line {}: material '{}': texture map '{}' is not supported, using Kd",
+                            line, name, map.display()));
+                    }
+                    self.materials.insert(name, m.to_material());
+                }
+            }
+            Err(e) => self.warnings.push(format!("line {}: material library '{}': {}", line, file, e)),
+        }
+    }
+
+    fn select_group(&mut self, name: &str) {
+        self.current = match self.groups.iter().position(|g| g.name == name) {
+            Some(i) => i,
+            None => {
+                self.groups.push(MeshGroup::new(name));
+                self.groups.len() - 1
+            }
+        };
+    }
+
+    fn parse_face(&mut self, line: usize, fields: SplitWhitespace) -> Result<(), MeshError> {
+        let face = fields
+            .map(|f| self.parse_face_vertex(line, f))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if face.len() < 3 {
+            return Err(MeshError::parse(line, format!(
+                "face needs at least 3 vertices, found {}", face.len())));
+        }
+
+        let smooth = face.iter().all(|v| v.normal.is_some());
+
+        // Polygons are split into a fan of triangles around the first vertex.
+        for i in 1..face.len() - 1 {
+            let (a, b, c) = (&face[0], &face[i], &face[i + 1]);
+            let (p1, p2, p3) = (self.vertices[a.vertex],
+                                self.vertices[b.vertex],
+                                self.vertices[c.vertex]);
+
+            let triangle: Box<dyn Shape> = if smooth {
+                Box::new(SmoothTriangle::new(p1, p2, p3,
+                                             self.normals[a.normal.unwrap()],
+                                             self.normals[b.normal.unwrap()],
+                                             self.normals[c.normal.unwrap()])
+                         .with_material(self.material.clone()))
+            } else {
+                Box::new(Triangle::new(p1, p2, p3).with_material(self.material.clone()))
+            };
+
+            self.groups[self.current].triangles.push(triangle);
+        }
+
+        Ok(())
+    }
+
+    // Accepts `v`, `v/vt`, `v//vn` and `v/vt/vn`.
+    fn parse_face_vertex(&self, line: usize, field: &str) -> Result<FaceVertex, MeshError> {
+        let parts: Vec<&str> = field.split('/').collect();
+
+        if parts.len() > 3 {
+            return Err(MeshError::parse(line, format!("invalid face vertex '{}'", field)));
+        }
+
+        let vertex = resolve_index(line, parts[0], self.vertices.len(), "vertex")?;
+
+        if let Some(vt) = parts.get(1).filter(|s| !s.is_empty()) {
+            resolve_index(line, vt, self.texcoords.len(), "texture coordinate")?;
+        }
+
+        let normal = match parts.get(2).filter(|s| !s.is_empty()) {
+            Some(vn) => Some(resolve_index(line, vn, self.normals.len(), "vertex normal")?),
+            None => None,
+        };
+
+        Ok(FaceVertex{vertex, normal})
+    }
+
+    fn finish(self) -> Mesh {
+        Mesh {
+            groups: self.groups.into_iter()
+                .filter(|g| !g.triangles.is_empty())
+                .collect(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn parse_floats(line: usize,
+                fields: SplitWhitespace,
+                min: usize,
+                max: usize,
+                what: &str) -> Result<Vec<f32>, MeshError> {
+    let values = fields
+        .map(|f| f.parse::<f32>()
+             .map_err(|_| MeshError::parse(line, format!("invalid number '{}' in {}", f, what))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if values.len() < min || values.len() > max {
+        return Err(MeshError::parse(line, format!(
+            "{} needs {} to {} values, found {}", what, min, max, values.len())));
+    }
+
+    Ok(values)
+}
+
+// OBJ indices are 1-based, and negative indices count back from the most
+// recently defined element.
+fn resolve_index(line: usize, field: &str, len: usize, what: &str) -> Result<usize, MeshError> {
+    let index = field.parse::<i64>()
+        .map_err(|_| MeshError::parse(line, format!("invalid {} index '{}'", what, field)))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(MeshError::parse(line, format!(
+            "{} index {} out of range ({} defined)", what, index, len)));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::rays::Ray;
+    use crate::transform::translation;
+    use crate::tup::Vector;
+
+    fn hit_normal(mesh: &Mesh, group: usize, triangle: usize, r: Ray) -> Normal {
+        let t = mesh.groups[group].triangles[triangle].as_ref();
+        let hit = t.intersect(r).hit().unwrap();
+        t.normal_at(r.position(hit.t), &hit)
+    }
+
+    #[test]
+    fn ignore_unrecognized() {
+        let source = "There was a young lady named Bright\n\
+                      who traveled much faster than light.\n\
+                      s 1\n\
+                      usemtl shiny\n";
+        let mesh = parse(source, Path::new("")).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 0);
+        assert_eq!(mesh.warnings, vec!["line 4: unknown material 'shiny'"]);
+    }
+
+    #[test]
+    fn vertices() {
+        let source = "v -1 1 0\n\
+                      v -1.0000 0.5000 0.0000\n\
+                      v 1 0 0\n\
+                      v 1 1 0\n";
+        let mut p = Parser::new(Path::new(""));
+        for (i, line) in source.lines().enumerate() {
+            p.parse_line(i + 1, line).unwrap();
+        }
+
+        assert_eq!(p.vertices, vec![Point::new(-1.0, 1.0, 0.0),
+                                    Point::new(-1.0, 0.5, 0.0),
+                                    Point::new(1.0, 0.0, 0.0),
+                                    Point::new(1.0, 1.0, 0.0)]);
+    }
+
+    #[test]
+    fn triangles() {
+        let source = "v -1 1 0\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      v 1 1 0\n\
+                      \n\
+                      f 1 2 3\n\
+                      f 1 3 4\n";
+        let mesh = parse(source, Path::new("")).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.groups[0].name, DEFAULT_GROUP);
+
+        let r = Ray::new(Point::new(-0.5, 0.25, -1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(mesh.groups[0].triangles[0].intersect(r).xs.len(), 1);
+        assert_eq!(mesh.groups[0].triangles[1].intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn polygon_fan() {
+        let source = "v -1 1 0\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      v 1 1 0\n\
+                      v 0 2 0\n\
+                      \n\
+                      f 1 2 3 4 5\n";
+        let mesh = parse(source, Path::new("")).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 3);
+
+        // The third triangle of the fan is (1, 4, 5), which is the only one
+        // containing a point near the top vertex.
+        let r = Ray::new(Point::new(0.0, 1.8, -1.0), Vector::new(0.0, 0.0, 1.0));
+        let hits: Vec<usize> = mesh.groups[0].triangles.iter()
+            .map(|t| t.intersect(r).xs.len())
+            .collect();
+        assert_eq!(hits, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn groups() {
+        let source = "v -1 1 0\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      v 1 1 0\n\
+                      g FirstGroup\n\
+                      f 1 2 3\n\
+                      o SecondGroup\n\
+                      f 1 3 4\n\
+                      g FirstGroup\n\
+                      f 1 2 4\n";
+        let mesh = parse(source, Path::new("")).unwrap();
+
+        assert_eq!(mesh.groups.len(), 2);
+        assert_eq!(mesh.group("FirstGroup").unwrap().triangles.len(), 2);
+        assert_eq!(mesh.group("SecondGroup").unwrap().triangles.len(), 1);
+        assert!(mesh.group(DEFAULT_GROUP).is_none());
+    }
+
+    #[test]
+    fn into_group() {
+        let source = "v -1 1 0\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      g First\n\
+                      f 1 2 3\n\
+                      g Second\n\
+                      f 1 3 2\n";
+        let g = parse(source, Path::new("")).unwrap()
+            .into_group(translation(0.0, 0.0, 5.0));
+
+        assert_eq!(g.children().len(), 2);
+
+        let r = Ray::new(Point::new(-0.5, 0.25, -1.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(r).xs;
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 6.0);
+    }
+
+    #[test]
+    fn face_forms() {
+        let source = "v 0 1 0\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      vt 0 0\n\
+                      vt 0.5 1\n\
+                      vn -1 0 0\n\
+                      vn 1 0 0\n\
+                      vn 0 1 0\n\
+                      f 1 2 3\n\
+                      f 1/1 2/2 3/1\n\
+                      f 1//3 2//1 3//2\n\
+                      f 1/1/3 2/2/1 3/1/2\n\
+                      f -3//-1 -2//-3 -1//-2\n\
+                      f 1// 2// 3//\n";
+        let mesh = parse(source, Path::new("")).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 6);
+
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let flat = Normal::new(0.0, 0.0, -1.0);
+        let smooth = Normal::new(-0.5547, 0.83205, 0.0);
+
+        assert_eq!(hit_normal(&mesh, 0, 0, r), flat);
+        assert_eq!(hit_normal(&mesh, 0, 1, r), flat);
+        assert_eq!(hit_normal(&mesh, 0, 2, r), smooth);
+        assert_eq!(hit_normal(&mesh, 0, 3, r), smooth);
+        assert_eq!(hit_normal(&mesh, 0, 4, r), smooth);
+        assert_eq!(hit_normal(&mesh, 0, 5, r), flat);
+    }
+
+    #[test]
+    fn comments() {
+        let source = "# a triangle\n\
+                      v 0 1 0 # top\n\
+                      v -1 0 0\n\
+                      v 1 0 0\n\
+                      f 1 2 3 # the only face\n";
+
+        assert_eq!(parse(source, Path::new("")).unwrap().triangle_count(), 1);
+    }
+
+    fn error_line(source: &str) -> usize {
+        match parse(source, Path::new("")) {
+            Err(MeshError::Parse { line, .. }) => line,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(error_line("v 1 2 3\nv 1 two 3\n"), 2);
+        assert_eq!(error_line("v 1 2\n"), 1);
+        assert_eq!(error_line("vn 1 2 3 4\n"), 1);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\n\nf 1 2\n"), 4);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 4\n"), 4);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 0 1 2\n"), 4);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1//1 2//1 3//1\n"), 4);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1/1/1/1 2 3\n"), 4);
+        assert_eq!(error_line("v 0 0 0\nv 1 0 0\nv 1 1 0\nf -4 2 3\n"), 4);
+    }
+
+    #[test]
+    fn error_message() {
+        let e = parse("v 1 2 3\nvt x\n", Path::new("")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2: invalid number 'x' in texture coordinate");
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("raytra-obj-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn materials() {
+        let dir = temp_dir("materials");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/colors.mtl"),
+                  "newmtl red\nKd 1 0 0\nNs 10\nnewmtl blue\nKd 0 0 1\n").unwrap();
+        fs::write(dir.join("model.obj"),
+                  "mtllib lib/colors.mtl\n\
+                   v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                   f 1 2 3\n\
+                   usemtl red\n\
+                   f 1 2 3\n\
+                   usemtl blue\n\
+                   f 1 2 3\n").unwrap();
+
+        let mesh = load(dir.join("model.obj")).unwrap();
+        let triangles = &mesh.groups[0].triangles;
+
+        assert_eq!(*triangles[0].material(), Material::default());
+        assert_eq!(triangles[1].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(triangles[1].material().shininess, 10.0);
+        assert_eq!(triangles[2].material().color, Color::new(0.0, 0.0, 1.0));
+        assert!(mesh.warnings.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn texture_map() {
+        let dir = temp_dir("texture_map");
+        fs::write(dir.join("wood.mtl"), "newmtl wood
+Kd 0.6 0.4 0.2
+map_Kd wood.ppm
+").unwrap();
+
+        let source = "mtllib wood.mtl
+                      v 0 1 0
+v -1 0 0
+v 1 0 0
+                      usemtl wood
+                      f 1 2 3
+";
+        let mesh = parse(source, &dir).unwrap();
+
+        assert_eq!(mesh.groups[0].triangles[0].material().color, Color::new(0.6, 0.4, 0.2));
+        assert_eq!(mesh.warnings, vec![format!(
+            "line 1: material 'wood': texture map '{}' is not supported, using Kd",
+            dir.join("wood.ppm").display())]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_materials() {
+        let dir = temp_dir("missing_materials");
+        fs::write(dir.join("colors.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+
+        let source = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                      mtllib nope.mtl\n\
+                      usemtl red\n\
+                      f 1 2 3\n";
+        let mesh = parse(source, &dir).unwrap();
+        assert_eq!(*mesh.groups[0].triangles[0].material(), Material::default());
+        assert_eq!(mesh.warnings.len(), 2);
+        assert!(mesh.warnings[0].starts_with("line 4: material library 'nope.mtl': "));
+        assert_eq!(mesh.warnings[1], "line 5: unknown material 'red'");
+
+        let source = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                      mtllib colors.mtl\n\
+                      usemtl red\n\
+                      f 1 2 3\n\
+                      usemtl green\n\
+                      f 1 2 3\n";
+        let mesh = parse(source, &dir).unwrap();
+        let triangles = &mesh.groups[0].triangles;
+        assert_eq!(triangles[0].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(*triangles[1].material(), Material::default());
+        assert_eq!(mesh.warnings, vec!["line 7: unknown material 'green'"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
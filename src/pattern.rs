@@ -0,0 +1,269 @@
+use std::fmt::Debug;
+
+use crate::color::Color;
+use crate::matrix::Matrix4x4;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::tup::Point;
+
+pub trait Pattern: Debug + Send + Sync {
+    fn transform(&self) -> &Transform;
+
+    fn set_transform(&mut self, transform: Transform);
+
+    // Like `Shape::with_transform`, for transforms from `Transform::new`
+    // that may not have been invertible.
+    fn with_transform(mut self, transform: Transform) -> Self where Self: Sized {
+        self.set_transform(transform);
+        self
+    }
+
+    // `point` is in pattern space.
+    fn pattern_at(&self, point: Point) -> Color;
+
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: Point) -> Color {
+        let object_point = object.world_to_object(world_point);
+        self.pattern_at(*self.transform().inverse() * object_point)
+    }
+}
+
+// Patterns are compared by identity, like shapes, so that materials
+// sharing one compare equal.
+impl PartialEq for dyn Pattern + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self as *const Self as *const u8,
+                     other as *const Self as *const u8)
+    }
+}
+
+fn is_even(n: f32) -> bool {
+    (n.floor() as i64).rem_euclid(2) == 0
+}
+
+fn pattern_transform(transform: Matrix4x4) -> Transform {
+    Transform::new(transform).expect("pattern transform is not invertible")
+}
+
+// Alternating bands of `a` and `b` one unit wide along x.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stripe {
+    transform: Transform,
+    a: Color,
+    b: Color,
+}
+
+impl Stripe {
+    pub fn new(a: Color, b: Color, transform: Matrix4x4) -> Stripe {
+        Stripe { transform: pattern_transform(transform), a, b }
+    }
+}
+
+impl Pattern for Stripe {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        if is_even(point.x()) { self.a } else { self.b }
+    }
+}
+
+// Blends from `a` to `b` along x, repeating every unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    transform: Transform,
+    a: Color,
+    b: Color,
+}
+
+impl Gradient {
+    pub fn new(a: Color, b: Color, transform: Matrix4x4) -> Gradient {
+        Gradient { transform: pattern_transform(transform), a, b }
+    }
+}
+
+impl Pattern for Gradient {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let fraction = point.x() - point.x().floor();
+        self.a + (self.b - self.a) * fraction
+    }
+}
+
+// Concentric rings around the y axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ring {
+    transform: Transform,
+    a: Color,
+    b: Color,
+}
+
+impl Ring {
+    pub fn new(a: Color, b: Color, transform: Matrix4x4) -> Ring {
+        Ring { transform: pattern_transform(transform), a, b }
+    }
+}
+
+impl Pattern for Ring {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let distance = (point.x().powi(2) + point.z().powi(2)).sqrt();
+        if is_even(distance) { self.a } else { self.b }
+    }
+}
+
+// Alternating unit cubes in all three dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checker {
+    transform: Transform,
+    a: Color,
+    b: Color,
+}
+
+impl Checker {
+    pub fn new(a: Color, b: Color, transform: Matrix4x4) -> Checker {
+        Checker { transform: pattern_transform(transform), a, b }
+    }
+}
+
+impl Pattern for Checker {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let sum = point.x().floor() + point.y().floor() + point.z().floor();
+        if is_even(sum) { self.a } else { self.b }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{BLACK, WHITE};
+    use crate::sphere::Sphere;
+    use crate::transform::*;
+
+    #[derive(Debug)]
+    struct TestPattern {
+        transform: Transform,
+    }
+
+    impl Pattern for TestPattern {
+        fn transform(&self) -> &Transform {
+            &self.transform
+        }
+
+        fn set_transform(&mut self, transform: Transform) {
+            self.transform = transform;
+        }
+
+        fn pattern_at(&self, point: Point) -> Color {
+            Color::new(point.x(), point.y(), point.z())
+        }
+    }
+
+    fn test_pattern(transform: Matrix4x4) -> TestPattern {
+        TestPattern { transform: pattern_transform(transform) }
+    }
+
+    #[test]
+    fn object_transform() {
+        let s = Sphere::new(scaling(2.0, 2.0, 2.0));
+        let p = test_pattern(Matrix4x4::identity());
+
+        assert_eq!(p.pattern_at_shape(&s, Point::new(2.0, 3.0, 4.0)), Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn transformed_pattern() {
+        let s = Sphere::unit();
+        let p = test_pattern(scaling(2.0, 2.0, 2.0));
+
+        assert_eq!(p.pattern_at_shape(&s, Point::new(2.0, 3.0, 4.0)), Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn both_transforms() {
+        let s = Sphere::new(scaling(2.0, 2.0, 2.0));
+        let p = test_pattern(translation(0.5, 1.0, 1.5));
+
+        assert_eq!(p.pattern_at_shape(&s, Point::new(2.5, 3.0, 3.5)), Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn stripe() {
+        let p = Stripe::new(WHITE, BLACK, Matrix4x4::identity());
+
+        assert_eq!(p.pattern_at(Point::new(0.0, 1.0, 2.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(0.9, 0.0, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(-0.1, 0.0, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(-1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(-1.1, 0.0, 0.0)), WHITE);
+    }
+
+    #[test]
+    fn gradient() {
+        let p = Gradient::new(WHITE, BLACK, Matrix4x4::identity());
+
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(0.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(p.pattern_at(Point::new(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(p.pattern_at(Point::new(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn stripe_with_transform() {
+        let t = Transform::new(scaling(2.0, 2.0, 2.0)).unwrap();
+        let p = Stripe::new(WHITE, BLACK, Matrix4x4::identity()).with_transform(t);
+
+        assert_eq!(*p.transform(), t);
+    }
+
+    #[test]
+    fn ring() {
+        let p = Ring::new(WHITE, BLACK, Matrix4x4::identity());
+
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 1.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(0.708, 0.0, 0.708)), BLACK);
+    }
+
+    #[test]
+    fn checker() {
+        let p = Checker::new(WHITE, BLACK, Matrix4x4::identity());
+
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(0.99, 0.0, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(1.01, 0.0, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.99, 0.0)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(0.0, 1.01, 0.0)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 0.99)), WHITE);
+        assert_eq!(p.pattern_at(Point::new(0.0, 0.0, 1.01)), BLACK);
+        assert_eq!(p.pattern_at(Point::new(-0.5, 0.0, 0.5)), BLACK);
+    }
+}
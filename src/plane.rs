@@ -0,0 +1,139 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Normal};
+use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::EPSILON;
+
+// The XZ plane in object space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plane {
+    transform: Transform,
+    material: Material,
+}
+
+impl Plane {
+    pub fn new(transform: Matrix4x4) -> Plane {
+        Plane {
+            transform: Transform::new(transform)
+                .expect("plane transform is not invertible"),
+            material: Material::default(),
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Plane {
+        Plane {
+            material,
+            ..self
+        }
+    }
+}
+
+impl Shape for Plane {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(Point::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+                    Point::new(f32::INFINITY, 0.0, f32::INFINITY))
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        // A ray parallel to the plane either misses it or lies within it;
+        // both count as a miss.
+        if ray.direction.y().abs() < EPSILON {
+            return Intersections::new(vec![]);
+        }
+
+        let t = -ray.origin.y() / ray.direction.y();
+        Intersections::new(vec![Intersection::new(t, self)])
+    }
+
+    fn local_normal_at(&self, _point: Point, _hit: &Intersection) -> Normal {
+        Normal::new(0.0, 1.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tup::Vector;
+    use crate::transform::*;
+
+    #[test]
+    fn normal_constant() {
+        let p = Plane::new(Matrix4x4::identity());
+        let i = Intersection::new(0.0, &p);
+
+        assert_eq!(p.local_normal_at(Point::new(0.0, 0.0, 0.0), &i), Normal::new(0.0, 1.0, 0.0));
+        assert_eq!(p.local_normal_at(Point::new(10.0, 0.0, -10.0), &i), Normal::new(0.0, 1.0, 0.0));
+        assert_eq!(p.local_normal_at(Point::new(-5.0, 0.0, 150.0), &i), Normal::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn intersect_parallel() {
+        let p = Plane::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 10.0, 0.0),
+                         Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(p.local_intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn intersect_coplanar() {
+        let p = Plane::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0),
+                         Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(p.local_intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn intersect_above() {
+        let p = Plane::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0),
+                         Vector::new(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(r);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert_eq!(xs.xs[0].t, 1.0);
+        assert_eq!(xs.xs[0].object, &p as &dyn Shape);
+    }
+
+    #[test]
+    fn intersect_below() {
+        let p = Plane::new(Matrix4x4::identity());
+        let r = Ray::new(Point::new(0.0, -1.0, 0.0),
+                         Vector::new(0.0, 1.0, 0.0));
+        let xs = p.local_intersect(r);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert_eq!(xs.xs[0].t, 1.0);
+        assert_eq!(xs.xs[0].object, &p as &dyn Shape);
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let p = Plane::new(translation(0.0, -1.0, 0.0));
+        let i = Intersection::new(0.0, &p);
+        let r = Ray::new(Point::new(0.0, 2.0, -3.0),
+                         Vector::new(0.0, -1.0, 1.0).norm());
+        let xs = p.intersect(r);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert!(abs_diff_eq!(xs.xs[0].t, 18.0_f32.sqrt(), epsilon=0.0001));
+        assert_eq!(p.normal_at(Point::new(3.0, -1.0, 3.0), &i), Normal::new(0.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,651 @@
+use std::fs;
+use std::path::Path;
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::mesh::{Mesh, MeshGroup, MeshError, DEFAULT_GROUP};
+use crate::shape::Shape;
+use crate::smooth_triangle::SmoothTriangle;
+use crate::triangle::Triangle;
+use crate::tup::{Point, Normal};
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh, MeshError> {
+    parse(&fs::read(path)?)
+}
+
+// Reads `vertex` elements with x/y/z and optional nx/ny/nz and
+// red/green/blue properties, and `face` elements with a vertex_indices
+// list. Other elements are read past and ignored.
+pub fn parse(data: &[u8]) -> Result<Mesh, MeshError> {
+    let header = Header::parse(data)?;
+    let mut body = Body::new(&data[header.body_start..], header.format, header.body_line);
+
+    let mut vertices = vec![];
+    let mut faces = vec![];
+
+    for element in &header.elements {
+        // The count comes from the file, so records are not preallocated
+        // from it; a short body runs out of data and fails instead.
+        // Elements without properties take up no space and are skipped.
+        let mut records = vec![];
+        if !element.properties.is_empty() {
+            for _ in 0..element.count {
+                records.push(body.read_record(element)?);
+            }
+        }
+
+        match element.name.as_str() {
+            "vertex" => vertices = read_vertices(element, &records)?,
+            "face" => faces = read_faces(element, &records)?,
+            _ => (),
+        }
+    }
+
+    build_mesh(&vertices, &faces)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        self == Scalar::F32 || self == Scalar::F64
+    }
+
+    // The value of a full-intensity color channel: the type's maximum for
+    // integers, 1 for floats.
+    fn color_scale(self) -> f32 {
+        match self {
+            Scalar::I8 => i8::MAX as f32,
+            Scalar::U8 => u8::MAX as f32,
+            Scalar::I16 => i16::MAX as f32,
+            Scalar::U16 => u16::MAX as f32,
+            Scalar::I32 => i32::MAX as f32,
+            Scalar::U32 => u32::MAX as f32,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PropertyKind {
+    Scalar(Scalar),
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, name: &str) -> Option<(usize, &Property)> {
+        self.properties.iter().enumerate().find(|(_, p)| p.name == name)
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    body_start: usize,
+    body_line: usize,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Header, MeshError> {
+        let mut format = None;
+        let mut elements: Vec<Element> = vec![];
+        let mut pos = 0;
+        let mut line = 0;
+
+        loop {
+            let end = data[pos..].iter().position(|&b| b == b'\n')
+                .map(|i| pos + i)
+                .ok_or_else(|| MeshError::Format(String::from("PLY header has no end_header")))?;
+
+            let text = String::from_utf8_lossy(&data[pos..end]);
+            let fields: Vec<&str> = text.split_whitespace().collect();
+            line += 1;
+            pos = end + 1;
+
+            if line == 1 {
+                if fields != ["ply"] {
+                    return Err(MeshError::parse(line, "not a PLY file"));
+                }
+                continue;
+            }
+
+            match fields.as_slice() {
+                ["format", name, _version] => {
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::BinaryLittleEndian,
+                        "binary_big_endian" => Format::BinaryBigEndian,
+                        _ => return Err(MeshError::parse(line, format!(
+                            "unsupported format '{}'", name))),
+                    });
+                }
+                ["element", name, count] => {
+                    let count = count.parse::<usize>()
+                        .map_err(|_| MeshError::parse(line, format!(
+                            "invalid element count '{}'", count)))?;
+                    elements.push(Element {
+                        name: name.to_string(),
+                        count,
+                        properties: vec![],
+                    });
+                }
+                ["property", rest @ ..] => {
+                    let element = elements.last_mut()
+                        .ok_or_else(|| MeshError::parse(line, "property before any element"))?;
+                    element.properties.push(parse_property(line, rest)?);
+                }
+                ["end_header"] => break,
+                ["comment", ..] | ["obj_info", ..] | [] => (),
+                _ => return Err(MeshError::parse(line, format!(
+                    "unrecognized header line '{}'", text.trim()))),
+            }
+        }
+
+        let format = format.ok_or_else(|| MeshError::parse(line, "PLY header has no format"))?;
+
+        Ok(Header {
+            format,
+            elements,
+            body_start: pos,
+            body_line: line,
+        })
+    }
+}
+
+fn parse_property(line: usize, fields: &[&str]) -> Result<Property, MeshError> {
+    let scalar = |name: &str| Scalar::from_name(name)
+        .ok_or_else(|| MeshError::parse(line, format!("unsupported property type '{}'", name)));
+
+    match fields {
+        ["list", count, item, name] => {
+            let count = scalar(count)?;
+            if count.is_float() {
+                return Err(MeshError::parse(line, format!(
+                    "list count type '{}' must be an integer type", fields[1])));
+            }
+            Ok(Property {
+                name: name.to_string(),
+                kind: PropertyKind::List(count, scalar(item)?),
+            })
+        }
+        [ty, name] => Ok(Property {
+            name: name.to_string(),
+            kind: PropertyKind::Scalar(scalar(ty)?),
+        }),
+        _ => Err(MeshError::parse(line, "malformed property")),
+    }
+}
+
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn scalar(&self) -> f64 {
+        match self {
+            Value::Scalar(v) => *v,
+            Value::List(_) => 0.0,
+        }
+    }
+}
+
+struct Body<'a> {
+    data: &'a [u8],
+    format: Format,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Body<'a> {
+    fn new(data: &'a [u8], format: Format, header_lines: usize) -> Body<'a> {
+        Body {
+            data,
+            format,
+            pos: 0,
+            line: header_lines,
+        }
+    }
+
+    fn read_record(&mut self, element: &Element) -> Result<Vec<Value>, MeshError> {
+        match self.format {
+            Format::Ascii => self.read_ascii_record(element),
+            _ => element.properties.iter()
+                .map(|p| self.read_binary_property(element, p))
+                .collect(),
+        }
+    }
+
+    // In ASCII files every element record sits on its own line.
+    fn read_ascii_record(&mut self, element: &Element) -> Result<Vec<Value>, MeshError> {
+        let text = loop {
+            if self.pos >= self.data.len() {
+                return Err(MeshError::Format(format!(
+                    "unexpected end of data in element '{}'", element.name)));
+            }
+
+            let end = self.data[self.pos..].iter().position(|&b| b == b'\n')
+                .map(|i| self.pos + i)
+                .unwrap_or(self.data.len());
+            let text = String::from_utf8_lossy(&self.data[self.pos..end]).into_owned();
+            self.pos = end + 1;
+            self.line += 1;
+
+            if !text.trim().is_empty() {
+                break text;
+            }
+        };
+
+        let line = self.line;
+        let mut tokens = text.split_whitespace();
+        let mut next = |what: &str| -> Result<f64, MeshError> {
+            let token = tokens.next()
+                .ok_or_else(|| MeshError::parse(line, format!(
+                    "missing value for '{}' in element '{}'", what, element.name)))?;
+            token.parse::<f64>()
+                .map_err(|_| MeshError::parse(line, format!("invalid number '{}'", token)))
+        };
+
+        element.properties.iter()
+            .map(|p| match p.kind {
+                PropertyKind::Scalar(_) => Ok(Value::Scalar(next(&p.name)?)),
+                PropertyKind::List(_, _) => {
+                    let count = next(&p.name)? as usize;
+                    let items = (0..count)
+                        .map(|_| next(&p.name))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(Value::List(items))
+                }
+            })
+            .collect()
+    }
+
+    fn read_binary_property(&mut self, element: &Element, property: &Property)
+                            -> Result<Value, MeshError> {
+        match property.kind {
+            PropertyKind::Scalar(ty) => Ok(Value::Scalar(self.read_binary(element, ty)?)),
+            PropertyKind::List(count, item) => {
+                let count = self.read_binary(element, count)? as usize;
+                let items = (0..count)
+                    .map(|_| self.read_binary(element, item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::List(items))
+            }
+        }
+    }
+
+    fn read_binary(&mut self, element: &Element, ty: Scalar) -> Result<f64, MeshError> {
+        let size = ty.size();
+        if self.pos + size > self.data.len() {
+            return Err(MeshError::Format(format!(
+                "unexpected end of data in element '{}'", element.name)));
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.data[self.pos..self.pos + size]);
+        self.pos += size;
+
+        // Normalize to little endian so each type is only decoded one way.
+        if self.format == Format::BinaryBigEndian {
+            bytes[..size].reverse();
+        }
+
+        let b = bytes;
+        Ok(match ty {
+            Scalar::I8 => b[0] as i8 as f64,
+            Scalar::U8 => b[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(b),
+        })
+    }
+}
+
+struct Vertex {
+    position: Point,
+    normal: Option<Normal>,
+    color: Option<Color>,
+}
+
+fn scalar_index(element: &Element, name: &str) -> Result<Option<(usize, Scalar)>, MeshError> {
+    match element.property(name) {
+        Some((i, p)) => match p.kind {
+            PropertyKind::Scalar(ty) => Ok(Some((i, ty))),
+            PropertyKind::List(_, _) => Err(MeshError::Format(format!(
+                "'{}' property of element '{}' must not be a list", name, element.name))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn read_vertices(element: &Element, records: &[Vec<Value>]) -> Result<Vec<Vertex>, MeshError> {
+    let required = |name: &str| scalar_index(element, name)?
+        .map(|(i, _)| i)
+        .ok_or_else(|| MeshError::Format(format!("vertex element has no '{}' property", name)));
+
+    let xyz = [required("x")?, required("y")?, required("z")?];
+
+    let normal = match (scalar_index(element, "nx")?,
+                        scalar_index(element, "ny")?,
+                        scalar_index(element, "nz")?) {
+        (Some((x, _)), Some((y, _)), Some((z, _))) => Some([x, y, z]),
+        _ => None,
+    };
+
+    let color = match (scalar_index(element, "red")?,
+                       scalar_index(element, "green")?,
+                       scalar_index(element, "blue")?) {
+        (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+        _ => None,
+    };
+
+    Ok(records.iter()
+       .map(|r| {
+           let get = |i: usize| r[i].scalar() as f32;
+
+           Vertex {
+               position: Point::new(get(xyz[0]), get(xyz[1]), get(xyz[2])),
+               normal: normal.map(|n| Normal::new(get(n[0]), get(n[1]), get(n[2]))),
+               color: color.map(|c| {
+                   let channel = |(i, ty): (usize, Scalar)| get(i) / ty.color_scale();
+                   Color::new(channel(c[0]), channel(c[1]), channel(c[2]))
+               }),
+           }
+       })
+       .collect())
+}
+
+fn read_faces(element: &Element, records: &[Vec<Value>]) -> Result<Vec<Vec<usize>>, MeshError> {
+    let index = element.property("vertex_indices")
+        .or_else(|| element.property("vertex_index"))
+        .map(|(i, _)| i)
+        .ok_or_else(|| MeshError::Format(String::from("face element has no vertex_indices property")))?;
+
+    records.iter()
+        .enumerate()
+        .map(|(f, r)| match &r[index] {
+            Value::List(items) => items.iter()
+                .map(|&i| {
+                    if i.is_finite() && i >= 0.0 && i.fract() == 0.0 {
+                        Ok(i as usize)
+                    } else {
+                        Err(MeshError::Format(format!(
+                            "face {} has invalid vertex index {}", f, i)))
+                    }
+                })
+                .collect(),
+            Value::Scalar(_) => Err(MeshError::Format(String::from(
+                "face vertex_indices property must be a list"))),
+        })
+        .collect()
+}
+
+fn build_mesh(vertices: &[Vertex], faces: &[Vec<usize>]) -> Result<Mesh, MeshError> {
+    let mut group = MeshGroup::new(DEFAULT_GROUP);
+
+    for (f, face) in faces.iter().enumerate() {
+        if face.len() < 3 {
+            return Err(MeshError::Format(format!(
+                "face {} needs at least 3 vertices, found {}", f, face.len())));
+        }
+        if let Some(i) = face.iter().find(|&&i| i >= vertices.len()) {
+            return Err(MeshError::Format(format!(
+                "face {} refers to vertex {}, but only {} are defined", f, i, vertices.len())));
+        }
+
+        // Quads and larger polygons are split into a fan of triangles.
+        for i in 1..face.len() - 1 {
+            let (a, b, c) = (&vertices[face[0]], &vertices[face[i]], &vertices[face[i + 1]]);
+
+            let material = match (a.color, b.color, c.color) {
+                (Some(ca), Some(cb), Some(cc)) => Material {
+                    color: (ca + cb + cc) * (1.0 / 3.0),
+                    ..Material::default()
+                },
+                _ => Material::default(),
+            };
+
+            let triangle: Box<dyn Shape> = match (a.normal, b.normal, c.normal) {
+                (Some(na), Some(nb), Some(nc)) => Box::new(
+                    SmoothTriangle::new(a.position, b.position, c.position, na, nb, nc)
+                        .with_material(material)),
+                _ => Box::new(
+                    Triangle::new(a.position, b.position, c.position)
+                        .with_material(material)),
+            };
+
+            group.triangles.push(triangle);
+        }
+    }
+
+    let groups = if group.triangles.is_empty() { vec![] } else { vec![group] };
+    Ok(Mesh { groups, warnings: vec![] })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intersection::Intersection;
+    use crate::mesh::parse_error;
+    use crate::rays::Ray;
+    use crate::tup::Vector;
+
+    const ASCII: &str = "ply\n\
+                         format ascii 1.0\n\
+                         comment a unit quad\n\
+                         element vertex 4\n\
+                         property float x\n\
+                         property float y\n\
+                         property float z\n\
+                         property uchar red\n\
+                         property uchar green\n\
+                         property uchar blue\n\
+                         element face 1\n\
+                         property list uchar int vertex_indices\n\
+                         end_header\n\
+                         -1 1 0 255 0 0\n\
+                         -1 -1 0 255 0 0\n\
+                         1 -1 0 255 0 0\n\
+                         1 1 0 0 0 255\n\
+                         4 0 1 2 3\n";
+
+    fn binary_header(format: &str) -> Vec<u8> {
+        format!("ply\n\
+                 format {} 1.0\n\
+                 element vertex 3\n\
+                 property float x\n\
+                 property float y\n\
+                 property float z\n\
+                 property double nx\n\
+                 property double ny\n\
+                 property double nz\n\
+                 element edge 1\n\
+                 property int vertex1\n\
+                 property int vertex2\n\
+                 element face 1\n\
+                 property list uchar uint vertex_indices\n\
+                 end_header\n", format).into_bytes()
+    }
+
+    fn binary_body(big_endian: bool) -> Vec<u8> {
+        let vertices: [[f32; 3]; 3] = [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        let normals: [[f64; 3]; 3] = [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        let mut body = vec![];
+
+        for (v, n) in vertices.iter().zip(normals.iter()) {
+            for x in v {
+                body.extend(if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+            }
+            for x in n {
+                body.extend(if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+            }
+        }
+        for x in [0_i32, 1].iter() {
+            body.extend(if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+        }
+        body.push(3);
+        for x in [0_u32, 1, 2].iter() {
+            body.extend(if big_endian { x.to_be_bytes() } else { x.to_le_bytes() });
+        }
+
+        body
+    }
+
+    #[test]
+    fn ascii() {
+        let mesh = parse(ASCII.as_bytes()).unwrap();
+        let triangles = &mesh.groups[0].triangles;
+
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(triangles[0].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(triangles[1].material().color, Color::new(2.0 / 3.0, 0.0, 1.0 / 3.0));
+
+        let r = Ray::new(Point::new(-0.5, -0.5, -1.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = triangles[0].intersect(r);
+        assert_eq!(xs.xs.len(), 1);
+        assert_eq!(xs.xs[0].t, 1.0);
+    }
+
+    #[test]
+    fn wide_colors() {
+        let data = ASCII.replace("property uchar ", "property ushort ").replace("255", "65535");
+        let mesh = parse(data.as_bytes()).unwrap();
+        let triangles = &mesh.groups[0].triangles;
+
+        assert_eq!(triangles[0].material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(triangles[1].material().color, Color::new(2.0 / 3.0, 0.0, 1.0 / 3.0));
+
+        let data = ASCII.replace("property uchar blue", "property float blue")
+            .replace("1 1 0 0 0 255", "1 1 0 0 0 1.0");
+        let mesh = parse(data.as_bytes()).unwrap();
+        assert_eq!(mesh.groups[0].triangles[1].material().color,
+                   Color::new(2.0 / 3.0, 0.0, 1.0 / 3.0));
+    }
+
+    #[test]
+    fn binary() {
+        for (format, big_endian) in [("binary_little_endian", false),
+                                     ("binary_big_endian", true)].iter() {
+            let mut data = binary_header(format);
+            data.extend(binary_body(*big_endian));
+
+            let mesh = parse(&data).unwrap();
+            assert_eq!(mesh.triangle_count(), 1);
+
+            let t = mesh.groups[0].triangles[0].as_ref();
+            let i = Intersection::with_uv(1.0, t, 0.45, 0.25);
+            assert_eq!(t.normal_at(Point::new(0.0, 0.0, 0.0), &i),
+                       Normal::new(-0.5547, 0.83205, 0.0));
+        }
+    }
+
+    #[test]
+    fn unsupported_type() {
+        let data = ASCII.replace("property uchar blue", "property uint24 blue");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 10: unsupported property type 'uint24'");
+
+        let data = ASCII.replace("list uchar int", "list float int");
+        assert_eq!(parse_error(parse(data.as_bytes())),
+                   "line 12: list count type 'float' must be an integer type");
+
+        let data = ASCII.replace("format ascii", "format binary_middle_endian");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 2: unsupported format 'binary_middle_endian'");
+    }
+
+    #[test]
+    fn malformed() {
+        let data = ASCII.replace("\n1 -1 0 255", "\n1 -1 zero 255");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 16: invalid number 'zero'");
+
+        let data = ASCII.replace("4 0 1 2 3", "4 0 1 2 7");
+        assert_eq!(parse_error(parse(data.as_bytes())),
+                   "face 0 refers to vertex 7, but only 4 are defined");
+
+        let data = ASCII.replace("property float x\n", "");
+        assert!(parse_error(parse(data.as_bytes())).contains("no 'x' property"));
+
+        let mut data = binary_header("binary_little_endian");
+        data.extend(&binary_body(false)[..20]);
+        assert_eq!(parse_error(parse(&data)), "unexpected end of data in element 'vertex'");
+
+        assert_eq!(parse_error(parse(b"solid cube\n")), "line 1: not a PLY file");
+    }
+
+    #[test]
+    fn short_body() {
+        let data = ASCII.replace("4 0 1 2 3\n", "");
+        assert_eq!(parse_error(parse(data.as_bytes())), "unexpected end of data in element 'face'");
+
+        let data = ASCII.replace("element face 1", "element face 18446744073709551615");
+        assert_eq!(parse_error(parse(data.as_bytes())), "unexpected end of data in element 'face'");
+    }
+
+    #[test]
+    fn invalid_face_index() {
+        let data = ASCII.replace("4 0 1 2 3", "4 -1 1 2 3");
+        assert_eq!(parse_error(parse(data.as_bytes())), "face 0 has invalid vertex index -1");
+
+        let data = ASCII.replace("4 0 1 2 3", "4 0 1.5 2 3");
+        assert_eq!(parse_error(parse(data.as_bytes())), "face 0 has invalid vertex index 1.5");
+
+        let data = ASCII.replace("4 0 1 2 3", "4 0 1 2 inf");
+        assert_eq!(parse_error(parse(data.as_bytes())), "face 0 has invalid vertex index inf");
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::Debug;
+
+use crate::bounds::Bounds;
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::rays::Ray;
+use crate::transform::Transform;
+use crate::tup::{Point, Normal};
+
+pub trait Shape: Debug {
+    // The full object-to-world transform, including those of any groups
+    // the shape has been added to.
+    fn transform(&self) -> &Transform;
+
+    // Replaces the object-to-world transform. Groups and CSG shapes pass
+    // the change on to their children.
+    fn set_transform(&mut self, transform: Transform);
+
+    // Builder form of `set_transform`. Together with `Transform::new` it
+    // gives a fallible way to place a shape:
+    // `Sphere::unit().with_transform(Transform::new(m)?)`.
+    fn with_transform(mut self, transform: Transform) -> Self where Self: Sized {
+        self.set_transform(transform);
+        self
+    }
+
+    fn material(&self) -> &Material;
+
+    // The shape's extent in object space.
+    fn local_bounds(&self) -> Bounds;
+
+    // `ray` and `point` are in object space.
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_>;
+
+    // `hit` is the intersection that produced `point`, for shapes whose
+    // normal depends on more than the position.
+    fn local_normal_at(&self, point: Point, hit: &Intersection) -> Normal;
+
+    fn intersect(&self, ray: Ray) -> Intersections<'_> {
+        self.local_intersect(ray.transform(self.transform().inverse()))
+    }
+
+    fn normal_at(&self, world_point: Point, hit: &Intersection) -> Normal {
+        let local_normal = self.local_normal_at(self.world_to_object(world_point), hit);
+        self.normal_to_world(local_normal)
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.local_bounds().transform(self.transform().matrix())
+    }
+
+    fn world_to_object(&self, point: Point) -> Point {
+        *self.transform().inverse() * point
+    }
+
+    fn normal_to_world(&self, normal: Normal) -> Normal {
+        *self.transform().inverse_transpose() * normal
+    }
+
+    // Whether `other` is this shape or, for composite shapes, one of the
+    // shapes it contains.
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::eq(self as *const Self as *const u8,
+                     other as *const dyn Shape as *const u8)
+    }
+}
+
+// Shapes are compared by identity: two intersections refer to the same
+// object only if they point at the same shape.
+impl PartialEq for dyn Shape + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self as *const Self as *const u8,
+                     other as *const Self as *const u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::Matrix4x4;
+    use crate::transform::*;
+    use crate::tup::Vector;
+    use std::cell::Cell;
+    use std::f32::consts::PI;
+
+    #[derive(Debug)]
+    struct TestShape {
+        transform: Transform,
+        material: Material,
+        saved_ray: Cell<Option<Ray>>,
+    }
+
+    impl TestShape {
+        fn new(transform: Matrix4x4) -> TestShape {
+            TestShape {
+                transform: Transform::new(transform).unwrap(),
+                material: Material::default(),
+                saved_ray: Cell::new(None),
+            }
+        }
+    }
+
+    impl Shape for TestShape {
+        fn transform(&self) -> &Transform {
+            &self.transform
+        }
+
+        fn set_transform(&mut self, transform: Transform) {
+            self.transform = transform;
+        }
+
+        fn material(&self) -> &Material {
+            &self.material
+        }
+
+        fn local_bounds(&self) -> Bounds {
+            Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+        }
+
+        fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+            self.saved_ray.set(Some(ray));
+            Intersections::new(vec![Intersection::new(1.0, self)])
+        }
+
+        fn local_normal_at(&self, point: Point, _hit: &Intersection) -> Normal {
+            Normal::new(point.x(), point.y(), point.z())
+        }
+    }
+
+    #[test]
+    fn intersect_scaled() {
+        let s = TestShape::new(scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        s.intersect(r);
+        let saved = s.saved_ray.get().unwrap();
+
+        assert_eq!(saved.origin, Point::new(0.0, 0.0, -2.5));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn intersect_translated() {
+        let s = TestShape::new(translation(5.0, 0.0, 0.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        s.intersect(r);
+        let saved = s.saved_ray.get().unwrap();
+
+        assert_eq!(saved.origin, Point::new(-5.0, 0.0, -5.0));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_translated() {
+        let s = TestShape::new(translation(0.0, 1.0, 0.0));
+        let i = Intersection::new(0.0, &s);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let n = s.normal_at(Point::new(0.0, 1.0 + a, -a), &i);
+
+        assert_eq!(n, Normal::new(0.0, a, -a));
+    }
+
+    #[test]
+    fn normal_transformed() {
+        let s = TestShape::new(Matrix4x4::identity()
+                               .rotate_z(PI / 5.0)
+                               .scale(1.0, 0.5, 1.0));
+        let i = Intersection::new(0.0, &s);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let n = s.normal_at(Point::new(0.0, a, -a), &i);
+
+        assert_eq!(n, Normal::new(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn with_transform() {
+        let t = Transform::new(translation(0.0, 1.0, 0.0)).unwrap();
+        let s = TestShape::new(Matrix4x4::identity()).with_transform(t);
+
+        assert_eq!(*s.transform(), t);
+    }
+
+    #[test]
+    fn identity() {
+        let a = TestShape::new(Matrix4x4::identity());
+        let b = TestShape::new(Matrix4x4::identity());
+
+        assert_eq!(&a as &dyn Shape, &a as &dyn Shape);
+        assert_ne!(&a as &dyn Shape, &b as &dyn Shape);
+    }
+}
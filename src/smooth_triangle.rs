@@ -0,0 +1,144 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Vector, Normal};
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::triangle::moller_trumbore;
+
+// A triangle with a normal at each vertex, interpolated across the face
+// using the barycentric coordinates of the hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    transform: Transform,
+    material: Material,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Normal,
+    n2: Normal,
+    n3: Normal,
+    e1: Vector,
+    e2: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point,
+               n1: Normal, n2: Normal, n3: Normal) -> SmoothTriangle {
+        SmoothTriangle {
+            transform: Transform::identity(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> SmoothTriangle {
+        SmoothTriangle {
+            material,
+            ..self
+        }
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::from_points(&[self.p1, self.p2, self.p3])
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        match moller_trumbore(ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::new(vec![]),
+        }
+    }
+
+    fn local_normal_at(&self, _point: Point, hit: &Intersection) -> Normal {
+        match hit.uv {
+            Some((u, v)) => {
+                let n = self.n2.to_vector() * u
+                    + self.n3.to_vector() * v
+                    + self.n1.to_vector() * (1.0 - u - v);
+                Normal::from_vector(n)
+            }
+            // Without barycentric coordinates fall back to the flat normal.
+            None => Normal::from_vector(self.e2.cross(self.e1).norm()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> SmoothTriangle {
+        SmoothTriangle::new(Point::new(0.0, 1.0, 0.0),
+                            Point::new(-1.0, 0.0, 0.0),
+                            Point::new(1.0, 0.0, 0.0),
+                            Normal::new(0.0, 1.0, 0.0),
+                            Normal::new(-1.0, 0.0, 0.0),
+                            Normal::new(1.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn new() {
+        let t = triangle();
+
+        assert_eq!(t.p1, Point::new(0.0, 1.0, 0.0));
+        assert_eq!(t.p2, Point::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.p3, Point::new(1.0, 0.0, 0.0));
+        assert_eq!(t.n1, Normal::new(0.0, 1.0, 0.0));
+        assert_eq!(t.n2, Normal::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.n3, Normal::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn intersect_uv() {
+        let t = triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(r);
+        let (u, v) = xs.xs[0].uv.unwrap();
+
+        assert_eq!(xs.xs.len(), 1);
+        assert!(abs_diff_eq!(u, 0.45, epsilon=0.00001));
+        assert!(abs_diff_eq!(v, 0.25, epsilon=0.00001));
+    }
+
+    #[test]
+    fn normal_interpolated() {
+        let t = triangle();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+
+        assert_eq!(t.normal_at(Point::new(0.0, 0.0, 0.0), &i),
+                   Normal::new(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn normal_without_uv() {
+        let t = triangle();
+        let i = Intersection::new(1.0, &t);
+
+        assert_eq!(t.normal_at(Point::new(0.0, 0.5, 0.0), &i),
+                   Normal::new(0.0, 0.0, -1.0));
+    }
+}
@@ -1,50 +1,78 @@
 use crate::rays::Ray;
-use crate::tup::Point;
+use crate::tup::{Point, Normal};
 use crate::matrix::Matrix4x4;
+use crate::bounds::Bounds;
 use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sphere {
-    transform: Matrix4x4,
+    transform: Transform,
+    material: Material,
 }
 
 impl Sphere {
     pub fn new(transform: Matrix4x4) -> Sphere {
         Sphere {
-            transform,
+            transform: Transform::new(transform)
+                .expect("sphere transform is not invertible"),
+            material: Material::default(),
         }
     }
 
     pub fn unit() -> Sphere {
+        Sphere::new(Matrix4x4::identity())
+    }
+
+    pub fn with_material(self, material: Material) -> Sphere {
         Sphere {
-            transform: Matrix4x4::identity(),
+            material,
+            ..self
         }
     }
+}
+
+impl Shape for Sphere {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
 
-    pub fn intersect(&self, ray: Ray) -> Intersections {
-        if let Some(inv) = self.transform.inverse() {
-            let ray_transform = ray.transform(&inv);
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
 
-            let sphere_to_ray_transform = ray_transform.origin - Point::new(0.0, 0.0, 0.0);
-            let a = ray_transform.direction.dot(ray_transform.direction);
-            let b = 2.0 * ray_transform.direction.dot(sphere_to_ray_transform);
-            let c = sphere_to_ray_transform.dot(sphere_to_ray_transform) - 1.0;
+    fn material(&self) -> &Material {
+        &self.material
+    }
 
-            let discriminant = b.powf(2.0) - (4.0 * a * c);
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
 
-            if discriminant < 0.0 {
-                Intersections::new(vec![])
-            } else {
-                let i1 = (-b - discriminant.sqrt()) / (2.0 * a);
-                let i2 = (-b + discriminant.sqrt()) / (2.0 * a);
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        let sphere_to_ray = ray.origin - Point::new(0.0, 0.0, 0.0);
+        let a = ray.direction.dot(ray.direction);
+        let b = 2.0 * ray.direction.dot(sphere_to_ray);
+        let c = sphere_to_ray.dot(sphere_to_ray) - 1.0;
 
-                Intersections::new(vec![Intersection::new(i1, *self),
-                                        Intersection::new(i2, *self)])
-            }
-        } else {
+        let discriminant = b.powf(2.0) - (4.0 * a * c);
+
+        if discriminant < 0.0 {
             Intersections::new(vec![])
+        } else {
+            let i1 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let i2 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+            Intersections::new(vec![Intersection::new(i1, self),
+                                    Intersection::new(i2, self)])
         }
     }
+
+    fn local_normal_at(&self, point: Point, _hit: &Intersection) -> Normal {
+        Normal::from_vector(point - Point::new(0.0, 0.0, 0.0))
+    }
 }
 
 
@@ -55,6 +83,7 @@ mod tests {
     use crate::rays::Ray;
     use crate::tup::{Point, Vector};
     use crate::transform::*;
+    use std::f32::consts::PI;
 
     #[test]
     fn intersect() {
@@ -122,14 +151,14 @@ mod tests {
         let is = s.intersect(r);
 
         assert_eq!(is.xs.len(), 2);
-        assert_eq!(is.xs[0].object, s);
-        assert_eq!(is.xs[1].object, s);
+        assert_eq!(is.xs[0].object, &s as &dyn Shape);
+        assert_eq!(is.xs[1].object, &s as &dyn Shape);
     }
 
     #[test]
     fn default_transform() {
         let s = Sphere::unit();
-        assert_eq!(s.transform, Matrix4x4::identity());
+        assert_eq!(*s.transform.matrix(), Matrix4x4::identity());
     }
 
     #[test]
@@ -137,7 +166,7 @@ mod tests {
         let t = translation(2.0, 3.0, 4.0);
         let s = Sphere::new(t);
 
-        assert_eq!(s.transform, t);
+        assert_eq!(*s.transform.matrix(), t);
     }
 
     #[test]
@@ -152,4 +181,64 @@ mod tests {
         assert_eq!(xs.xs[0].t, 3.0);
         assert_eq!(xs.xs[1].t, 7.0);
     }
+
+    #[test]
+    fn normal_axes() {
+        let s = Sphere::unit();
+        let i = Intersection::new(0.0, &s);
+
+        assert_eq!(s.normal_at(Point::new(1.0, 0.0, 0.0), &i), Normal::new(1.0, 0.0, 0.0));
+        assert_eq!(s.normal_at(Point::new(0.0, 1.0, 0.0), &i), Normal::new(0.0, 1.0, 0.0));
+        assert_eq!(s.normal_at(Point::new(0.0, 0.0, 1.0), &i), Normal::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_nonaxial() {
+        let s = Sphere::unit();
+        let i = Intersection::new(0.0, &s);
+        let a = 3.0_f32.sqrt() / 3.0;
+        let n = s.normal_at(Point::new(a, a, a), &i);
+
+        assert_eq!(n, Normal::new(a, a, a));
+        assert_eq!(n, n.norm());
+    }
+
+    #[test]
+    fn normal_translated() {
+        let s = Sphere::new(translation(0.0, 1.0, 0.0));
+        let i = Intersection::new(0.0, &s);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let n = s.normal_at(Point::new(0.0, 1.0 + a, -a), &i);
+
+        assert_eq!(n, Normal::new(0.0, a, -a));
+    }
+
+    #[test]
+    fn normal_transformed() {
+        let s = Sphere::new(Matrix4x4::identity()
+                            .rotate_z(PI / 5.0)
+                            .scale(1.0, 0.5, 1.0));
+        let i = Intersection::new(0.0, &s);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let n = s.normal_at(Point::new(0.0, a, -a), &i);
+
+        assert_eq!(n, Normal::new(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn default_material() {
+        let s = Sphere::unit();
+        assert_eq!(*s.material(), Material::default());
+    }
+
+    #[test]
+    fn assign_material() {
+        let m = Material {
+            ambient: 1.0,
+            ..Material::default()
+        };
+        let s = Sphere::unit().with_material(m.clone());
+
+        assert_eq!(*s.material(), m);
+    }
 }
@@ -0,0 +1,303 @@
+use std::fs;
+use std::path::Path;
+
+use crate::mesh::{Mesh, MeshGroup, MeshError, DEFAULT_GROUP};
+use crate::triangle::Triangle;
+use crate::tup::{Point, Normal};
+
+const HEADER_SIZE: usize = 80;
+const RECORD_SIZE: usize = 50;
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Mesh, MeshError> {
+    parse(&fs::read(path)?)
+}
+
+// Binary files may also start with "solid", so the format is decided by
+// whether the size matches the triangle count in the binary header, or by
+// bytes that can't appear in a text file. Text files may lead with a BOM
+// or whitespace before "solid".
+pub fn parse(data: &[u8]) -> Result<Mesh, MeshError> {
+    let text = data.strip_prefix(BOM).unwrap_or(data);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(text.len());
+
+    if is_binary(data) || !text[start..].starts_with(b"solid") {
+        parse_binary(data)
+    } else {
+        parse_ascii(&String::from_utf8_lossy(text))
+    }
+}
+
+fn is_binary(data: &[u8]) -> bool {
+    let is_text = |b: u8| (b >= 0x20 && b != 0x7f) || matches!(b, b'\t' | b'\n' | b'\r');
+    if !data.iter().all(|&b| is_text(b)) {
+        return true;
+    }
+
+    if data.len() < HEADER_SIZE + 4 {
+        return false;
+    }
+
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    data.len() == HEADER_SIZE + 4 + count * RECORD_SIZE
+}
+
+fn parse_binary(data: &[u8]) -> Result<Mesh, MeshError> {
+    if data.len() < HEADER_SIZE + 4 {
+        return Err(MeshError::Format(String::from("binary STL is missing its header")));
+    }
+
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    let expected = HEADER_SIZE + 4 + count * RECORD_SIZE;
+    if data.len() < expected {
+        return Err(MeshError::Format(format!(
+            "binary STL declares {} triangles but has data for {}",
+            count, (data.len() - HEADER_SIZE - 4) / RECORD_SIZE)));
+    }
+
+    let mut group = MeshGroup::new(DEFAULT_GROUP);
+
+    for record in data[HEADER_SIZE + 4..expected].chunks(RECORD_SIZE) {
+        let f = |i: usize| {
+            let b = &record[i * 4..i * 4 + 4];
+            f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        };
+
+        // The trailing two-byte attribute count is unused.
+        let normal = Normal::new(f(0), f(1), f(2));
+        let triangle = Triangle::new(Point::new(f(3), f(4), f(5)),
+                                     Point::new(f(6), f(7), f(8)),
+                                     Point::new(f(9), f(10), f(11)))
+            .with_normal(normal);
+
+        group.triangles.push(Box::new(triangle));
+    }
+
+    let groups = if group.triangles.is_empty() { vec![] } else { vec![group] };
+    Ok(Mesh { groups, warnings: vec![] })
+}
+
+// Each `solid` block becomes a group named after the solid.
+fn parse_ascii(source: &str) -> Result<Mesh, MeshError> {
+    let mut groups: Vec<MeshGroup> = vec![];
+    let mut in_solid = false;
+    let mut normal: Option<Normal> = None;
+    let mut vertices: Vec<Point> = vec![];
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let fields: Vec<&str> = text.split_whitespace().collect();
+
+        match fields.as_slice() {
+            ["solid", name @ ..] => {
+                let name = if name.is_empty() { DEFAULT_GROUP.to_string() } else { name.join(" ") };
+                groups.push(MeshGroup::new(&name));
+                in_solid = true;
+            }
+            ["endsolid", ..] => {
+                if !in_solid {
+                    return Err(MeshError::parse(line, "endsolid without a solid"));
+                }
+                if normal.is_some() {
+                    return Err(MeshError::parse(line, "endsolid inside a facet"));
+                }
+                in_solid = false;
+            }
+            ["facet", "normal", xyz @ ..] => {
+                if !in_solid {
+                    return Err(MeshError::parse(line, "facet outside of a solid"));
+                }
+                let n = parse_floats(line, xyz, "facet normal")?;
+                normal = Some(Normal::new(n[0], n[1], n[2]));
+                vertices.clear();
+            }
+            ["outer", "loop"] | ["endloop"] | [] => (),
+            ["vertex", xyz @ ..] => {
+                if normal.is_none() {
+                    return Err(MeshError::parse(line, "vertex outside of a facet"));
+                }
+                let v = parse_floats(line, xyz, "vertex")?;
+                vertices.push(Point::new(v[0], v[1], v[2]));
+            }
+            ["endfacet"] => {
+                let n = normal.take()
+                    .ok_or_else(|| MeshError::parse(line, "endfacet without a facet"))?;
+                if vertices.len() != 3 {
+                    return Err(MeshError::parse(line, format!(
+                        "facet needs 3 vertices, found {}", vertices.len())));
+                }
+
+                let triangle = Triangle::new(vertices[0], vertices[1], vertices[2])
+                    .with_normal(n);
+                groups.last_mut().unwrap().triangles.push(Box::new(triangle));
+            }
+            _ => return Err(MeshError::parse(line, format!(
+                "unexpected '{}'", text.trim()))),
+        }
+    }
+
+    if normal.is_some() {
+        return Err(MeshError::Format(String::from("STL ends inside a facet")));
+    }
+
+    Ok(Mesh {
+        groups: groups.into_iter()
+            .filter(|g| !g.triangles.is_empty())
+            .collect(),
+        warnings: vec![],
+    })
+}
+
+fn parse_floats(line: usize, fields: &[&str], what: &str) -> Result<Vec<f32>, MeshError> {
+    if fields.len() != 3 {
+        return Err(MeshError::parse(line, format!(
+            "{} needs 3 values, found {}", what, fields.len())));
+    }
+
+    fields.iter()
+        .map(|f| f.parse::<f32>()
+             .map_err(|_| MeshError::parse(line, format!("invalid number '{}' in {}", f, what))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intersection::Intersection;
+    use crate::mesh::parse_error;
+    use crate::rays::Ray;
+    use crate::tup::Vector;
+
+    const ASCII: &str = "solid wedge\n\
+                         facet normal 0 0 -1\n\
+                         \x20 outer loop\n\
+                         \x20   vertex 0 1 0\n\
+                         \x20   vertex 1 0 0\n\
+                         \x20   vertex -1 0 0\n\
+                         \x20 endloop\n\
+                         endfacet\n\
+                         facet normal 0 0 0\n\
+                         \x20 outer loop\n\
+                         \x20   vertex 0 1 1\n\
+                         \x20   vertex -1 0 1\n\
+                         \x20   vertex 1 0 1\n\
+                         \x20 endloop\n\
+                         endfacet\n\
+                         endsolid wedge\n";
+
+    fn binary(header: &[u8], triangles: &[[f32; 12]]) -> Vec<u8> {
+        let mut data = vec![0u8; HEADER_SIZE];
+        data[..header.len()].copy_from_slice(header);
+        data.extend(&(triangles.len() as u32).to_le_bytes());
+
+        for t in triangles {
+            for x in t.iter() {
+                data.extend(&x.to_le_bytes());
+            }
+            data.extend(&[0, 0]);
+        }
+
+        data
+    }
+
+    fn normal(mesh: &Mesh, i: usize) -> Normal {
+        let t = mesh.groups[0].triangles[i].as_ref();
+        t.normal_at(Point::new(0.0, 0.0, 0.0), &Intersection::new(0.0, t))
+    }
+
+    #[test]
+    fn ascii() {
+        let mesh = parse(ASCII.as_bytes()).unwrap();
+
+        assert_eq!(mesh.groups.len(), 1);
+        assert_eq!(mesh.groups[0].name, "wedge");
+        assert_eq!(mesh.triangle_count(), 2);
+
+        // The first facet's normal is taken from the file even though its
+        // winding would give the opposite one; the second has none and
+        // falls back to the winding.
+        assert_eq!(normal(&mesh, 0), Normal::new(0.0, 0.0, -1.0));
+        assert_eq!(normal(&mesh, 1), Normal::new(0.0, 0.0, -1.0));
+
+        let r = Ray::new(Point::new(0.0, 0.5, -1.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(mesh.groups[0].triangles[1].intersect(r).xs[0].t, 2.0);
+    }
+
+    #[test]
+    fn ascii_with_bom() {
+        let data = format!("\u{feff}\n  {}", ASCII);
+        let mesh = parse(data.as_bytes()).unwrap();
+
+        assert_eq!(mesh.groups[0].name, "wedge");
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let data = format!("\u{feff}{}", ASCII.replace("vertex 1 0 0", "vertex 1 0"));
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 5: vertex needs 3 values, found 2");
+    }
+
+    #[test]
+    fn endsolid() {
+        let data = format!("{}{}", ASCII, ASCII.replace("wedge", "copy"));
+        let mesh = parse(data.as_bytes()).unwrap();
+        assert_eq!(mesh.groups.len(), 2);
+        assert_eq!(mesh.group("copy").unwrap().triangles.len(), 2);
+
+        let facet = ASCII.lines().skip(1).take(7).collect::<Vec<_>>().join("\n");
+        let data = format!("{}{}\n", ASCII, facet);
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 17: facet outside of a solid");
+
+        let data = ASCII.replace("endfacet\nendsolid", "endsolid");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 15: endsolid inside a facet");
+
+        let data = format!("{}endsolid wedge\n", ASCII);
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 17: endsolid without a solid");
+    }
+
+    #[test]
+    fn binary_triangles() {
+        let data = binary(b"exported by some CAD tool",
+                          &[[0.0, 0.0, 1.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                            [0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, -1.0, 0.0, 1.0]]);
+        let mesh = parse(&data).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(normal(&mesh, 0), Normal::new(0.0, 0.0, 1.0));
+        assert_eq!(normal(&mesh, 1), Normal::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn binary_starting_with_solid() {
+        let data = binary(b"solid but actually binary",
+                          &[[0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0]]);
+        let mesh = parse(&data).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 1);
+        assert_eq!(normal(&mesh, 0), Normal::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn malformed() {
+        let data = ASCII.replace("vertex 1 0 0", "vertex 1 0");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 5: vertex needs 3 values, found 2");
+
+        let data = ASCII.replace("    vertex -1 0 0\n", "");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 7: facet needs 3 vertices, found 2");
+
+        let data = ASCII.replace("outer loop", "inner loop");
+        assert_eq!(parse_error(parse(data.as_bytes())), "line 3: unexpected 'inner loop'");
+
+        let mut data = binary(b"", &[[0.0; 12], [0.0; 12]]);
+        data.truncate(data.len() - 10);
+        assert_eq!(parse_error(parse(&data)), "binary STL declares 2 triangles but has data for 1");
+    }
+
+    #[test]
+    fn short_binary_starting_with_solid() {
+        let mut data = binary(b"solid but actually binary",
+                              &[[0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0],
+                                [0.0; 12]]);
+        data.truncate(data.len() - 10);
+
+        assert_eq!(parse_error(parse(&data)), "binary STL declares 2 triangles but has data for 1");
+    }
+}
@@ -1,4 +1,48 @@
+use std::ops::Mul;
+
 use crate::matrix::*;
+use crate::tup::{Point, Vector};
+
+// A transform matrix along with the inverse and inverse-transpose that
+// intersection and shading need, computed once up front.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4x4,
+    inverse: Matrix4x4,
+    inverse_transpose: Matrix4x4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4x4) -> Option<Transform> {
+        let inverse = matrix.inverse()?;
+
+        Some(Transform {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+
+    pub fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4x4::identity(),
+            inverse: Matrix4x4::identity(),
+            inverse_transpose: Matrix4x4::identity(),
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4x4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4x4 {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix4x4 {
+        &self.inverse_transpose
+    }
+}
 
 pub fn translation(x: f32, y: f32, z: f32) -> Matrix4x4 {
     let mut trans = Matrix4x4::identity();
@@ -68,6 +112,36 @@ pub fn shearing(xy: f32, xz: f32,
     trans
 }
 
+pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix4x4 {
+    let forward = (to - from).norm();
+    let left = forward.cross(up.norm());
+    let true_up = left.cross(forward);
+
+    let orientation = Matrix4x4::new(left.x(), left.y(), left.z(), 0.0,
+                                     true_up.x(), true_up.y(), true_up.z(), 0.0,
+                                     -forward.x(), -forward.y(), -forward.z(), 0.0,
+                                     0.0, 0.0, 0.0, 1.0);
+
+    orientation * translation(-from.x(), -from.y(), -from.z())
+}
+
+// Applies `rhs` first, then `self`. The inverse of a product is the
+// product of the inverses in reverse order, so composing never inverts a
+// matrix and can't fail.
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        let inverse = rhs.inverse * self.inverse;
+
+        Transform {
+            matrix: self.matrix * rhs.matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        }
+    }
+}
+
 impl Matrix4x4 {
     pub fn translate(self, x: f32, y: f32, z: f32) -> Matrix4x4 {
         translation(x, y, z) * self
@@ -100,7 +174,6 @@ impl Matrix4x4 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tup::*;
     use std::f32::consts::PI;
 
     #[test]
@@ -197,4 +270,66 @@ mod tests {
                    Point::new(15.0, 0.0, 7.0));
         assert_eq!(t * p, Point::new(15.0, 0.0, 7.0));
     }
+
+    #[test]
+    fn transform() {
+        let m = translation(1.0, 2.0, 3.0).scale(2.0, 2.0, 2.0);
+        let t = Transform::new(m).unwrap();
+
+        assert_eq!(*t.matrix(), m);
+        assert_eq!(*t.inverse(), m.inverse().unwrap());
+        assert_eq!(*t.inverse_transpose(), m.inverse().unwrap().transpose());
+
+        assert_eq!(Transform::new(scaling(0.0, 1.0, 1.0)), None);
+        assert_eq!(Transform::new(Matrix4x4::identity()).unwrap(), Transform::identity());
+    }
+
+    #[test]
+    fn compose() {
+        let a = translation(1.0, 2.0, 3.0);
+        let b = rotation_y(1.0).scale(2.0, 1.0, 0.5);
+        let t = Transform::new(a).unwrap() * Transform::new(b).unwrap();
+
+        assert_eq!(t, Transform::new(a * b).unwrap());
+    }
+
+    #[test]
+    fn view_default() {
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+
+        assert_eq!(view_transform(from, to, up), Matrix4x4::identity());
+    }
+
+    #[test]
+    fn view_positive_z() {
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+
+        assert_eq!(view_transform(from, to, up), scaling(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn view_moves_world() {
+        let from = Point::new(0.0, 0.0, 8.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+
+        assert_eq!(view_transform(from, to, up), translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn view_arbitrary() {
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+
+        assert_eq!(view_transform(from, to, up),
+                   Matrix4x4::new(-0.50709, 0.50709, 0.67612, -2.36643,
+                                  0.76772, 0.60609, 0.12122, -2.82843,
+                                  -0.35857, 0.59761, -0.71714, 0.00000,
+                                  0.00000, 0.00000, 0.00000, 1.00000));
+    }
 }
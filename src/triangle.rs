@@ -0,0 +1,215 @@
+use crate::rays::Ray;
+use crate::tup::{Point, Vector, Normal};
+use crate::bounds::Bounds;
+use crate::intersection::*;
+use crate::material::Material;
+use crate::shape::Shape;
+use crate::transform::Transform;
+use crate::EPSILON;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    transform: Transform,
+    material: Material,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Normal,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Triangle {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
+        Triangle {
+            transform: Transform::identity(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: Normal::from_vector(e2.cross(e1).norm()),
+        }
+    }
+
+    pub fn with_material(self, material: Material) -> Triangle {
+        Triangle {
+            material,
+            ..self
+        }
+    }
+
+    // Overrides the normal computed from the winding order, e.g. with one
+    // supplied by a model file. Zero-length normals are ignored.
+    pub fn with_normal(self, normal: Normal) -> Triangle {
+        if normal.mag() == 0.0 {
+            return self;
+        }
+
+        Triangle {
+            normal: normal.norm(),
+            ..self
+        }
+    }
+}
+
+// Möller–Trumbore ray/triangle intersection. Returns `t` along with the
+// barycentric `u` and `v` of the hit, weighting the second and third
+// vertices respectively.
+pub(crate) fn moller_trumbore(ray: Ray, p1: Point, e1: Vector, e2: Vector)
+                              -> Option<(f32, f32, f32)> {
+    let dir_cross_e2 = ray.direction.cross(e2);
+    let det = e1.dot(dir_cross_e2);
+
+    // Rays parallel or nearly parallel to the plane would divide into huge
+    // t, u and v values, which shows up as acne on meshes.
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.direction.dot(origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
+    }
+
+    Some((f * e2.dot(origin_cross_e1), u, v))
+}
+
+impl Shape for Triangle {
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::from_points(&[self.p1, self.p2, self.p3])
+    }
+
+    fn local_intersect(&self, ray: Ray) -> Intersections<'_> {
+        match moller_trumbore(ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::new(vec![]),
+        }
+    }
+
+    fn local_normal_at(&self, _point: Point, _hit: &Intersection) -> Normal {
+        self.normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> Triangle {
+        Triangle::new(Point::new(0.0, 1.0, 0.0),
+                      Point::new(-1.0, 0.0, 0.0),
+                      Point::new(1.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn new() {
+        let t = triangle();
+
+        assert_eq!(t.p1, Point::new(0.0, 1.0, 0.0));
+        assert_eq!(t.p2, Point::new(-1.0, 0.0, 0.0));
+        assert_eq!(t.p3, Point::new(1.0, 0.0, 0.0));
+        assert_eq!(t.e1, Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, Vector::new(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, Normal::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn with_normal() {
+        let t = triangle().with_normal(Normal::new(0.0, 0.0, 2.0));
+        assert_eq!(t.normal, Normal::new(0.0, 0.0, 1.0));
+
+        let t = triangle().with_normal(Normal::new(0.0, 0.0, 0.0));
+        assert_eq!(t.normal, Normal::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal() {
+        let t = triangle();
+        let i = Intersection::new(0.0, &t);
+
+        assert_eq!(t.local_normal_at(Point::new(0.0, 0.5, 0.0), &i), t.normal);
+        assert_eq!(t.local_normal_at(Point::new(-0.5, 0.75, 0.0), &i), t.normal);
+        assert_eq!(t.local_normal_at(Point::new(0.5, 0.25, 0.0), &i), t.normal);
+    }
+
+    #[test]
+    fn intersect_parallel() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0),
+                         Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(t.local_intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn intersect_grazing() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, -1.5, -0.00002),
+                         Vector::new(0.0, 1.0, 0.00001));
+
+        assert_eq!(t.local_intersect(r).xs.len(), 0);
+    }
+
+    #[test]
+    fn intersect_miss_edges() {
+        let t = triangle();
+        let origins = [Point::new(1.0, 1.0, -2.0),
+                       Point::new(-1.0, 1.0, -2.0),
+                       Point::new(0.0, -1.0, -2.0)];
+
+        for origin in origins.iter() {
+            let r = Ray::new(*origin, Vector::new(0.0, 0.0, 1.0));
+            assert_eq!(t.local_intersect(r).xs.len(), 0);
+        }
+    }
+
+    #[test]
+    fn intersect() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(r);
+
+        assert_eq!(xs.xs.len(), 1);
+        assert_eq!(xs.xs[0].t, 2.0);
+        assert_eq!(xs.xs[0].uv, Some((0.25, 0.25)));
+    }
+
+    #[test]
+    fn barycentric() {
+        let t = triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(r);
+        let (u, v) = xs.xs[0].uv.unwrap();
+
+        assert!(abs_diff_eq!(xs.xs[0].t, 2.0, epsilon=0.00001));
+        assert!(abs_diff_eq!(u, 0.45, epsilon=0.00001));
+        assert!(abs_diff_eq!(v, 0.25, epsilon=0.00001));
+    }
+}
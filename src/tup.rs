@@ -1,6 +1,8 @@
 use std::cmp::PartialEq;
 use std::ops::{Add, Sub, Neg, Mul};
 
+use crate::EPSILON;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Tup {
     x: f32,
@@ -127,6 +129,63 @@ impl Vector {
             self.0.z * other.0.x - self.0.x * other.0.z,
             self.0.x * other.0.y - self.0.y * other.0.x)
     }
+
+    // Mirrors the vector about `normal`, which must be unit length.
+    pub fn reflect(&self, normal: Normal) -> Vector {
+        let n = normal.to_vector();
+        *self - n * 2.0 * self.dot(n)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Normal(Tup);
+
+impl Normal {
+    pub fn new(x: f32, y: f32, z: f32) -> Normal {
+        Normal(Tup::new(x, y, z, 0.0))
+    }
+
+    // Transforming a normal by an inverse-transpose can leave garbage in w,
+    // so it is always dropped here.
+    pub fn from_tup(t: Tup) -> Normal {
+        Normal::new(t.x, t.y, t.z)
+    }
+
+    pub fn from_vector(v: Vector) -> Normal {
+        Normal(v.0)
+    }
+
+    pub fn to_tup(self) -> Tup {
+        self.0
+    }
+
+    pub fn to_vector(self) -> Vector {
+        Vector(self.0)
+    }
+
+    pub fn x(&self) -> f32 {
+        self.0.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.0.y
+    }
+
+    pub fn z(&self) -> f32 {
+        self.0.z
+    }
+
+    pub fn mag(&self) -> f32 {
+        self.0.mag()
+    }
+
+    pub fn norm(&self) -> Normal {
+        Normal(self.0.norm())
+    }
+
+    pub fn dot(&self, other: Vector) -> f32 {
+        self.0.dot(other.0)
+    }
 }
 
 
@@ -208,6 +267,14 @@ impl Neg for Vector {
     }
 }
 
+impl Neg for Normal {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Normal(-self.0)
+    }
+}
+
 impl Mul<f32> for Tup {
     type Output = Self;
 
@@ -232,10 +299,10 @@ impl Mul<f32> for Vector {
 
 impl PartialEq for Tup {
     fn eq(&self, other: &Self) -> bool {
-        abs_diff_eq!(self.x, other.x)
-            && abs_diff_eq!(self.y, other.y)
-            && abs_diff_eq!(self.z, other.z)
-            && abs_diff_eq!(self.w, other.w)
+        abs_diff_eq!(self.x, other.x, epsilon=EPSILON)
+            && abs_diff_eq!(self.y, other.y, epsilon=EPSILON)
+            && abs_diff_eq!(self.z, other.z, epsilon=EPSILON)
+            && abs_diff_eq!(self.w, other.w, epsilon=EPSILON)
     }
 }
 
@@ -275,6 +342,18 @@ impl PartialEq<Tup> for Vector {
     }
 }
 
+impl PartialEq for Normal {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl PartialEq<Tup> for Normal {
+    fn eq(&self, other: &Tup) -> bool {
+        self.0 == *other
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -389,10 +468,10 @@ mod tests {
         assert_eq!(a.mag(), 1.0);
 
         let a = Vector::new(1.0, 2.0, 3.0);
-        assert_eq!(a.mag(), (14.0 as f32).sqrt());
+        assert_eq!(a.mag(), 14.0_f32.sqrt());
 
         let a = Vector::new(-1.0, -2.0, -3.0);
-        assert_eq!(a.mag(), (14.0 as f32).sqrt());
+        assert_eq!(a.mag(), 14.0_f32.sqrt());
     }
 
     #[test]
@@ -421,4 +500,30 @@ mod tests {
         assert_eq!(a.cross(b), Vector::new(-1.0, 2.0, -1.0));
         assert_eq!(b.cross(a), Vector::new(1.0, -2.0, 1.0));
     }
+
+    #[test]
+    fn reflect() {
+        let v = Vector::new(1.0, -1.0, 0.0);
+        let n = Normal::new(0.0, 1.0, 0.0);
+        assert_eq!(v.reflect(n), Vector::new(1.0, 1.0, 0.0));
+
+        let a = 2.0_f32.sqrt() / 2.0;
+        let v = Vector::new(0.0, -1.0, 0.0);
+        let n = Normal::new(a, a, 0.0);
+        assert_eq!(v.reflect(n), Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn normal() {
+        let n = Normal::new(4.0, -4.0, 3.0);
+        assert_eq!(n, Tup::new(4.0, -4.0, 3.0, 0.0));
+
+        let n = Normal::from_tup(Tup::new(1.0, 2.0, 3.0, 7.0));
+        assert_eq!(n, Normal::new(1.0, 2.0, 3.0));
+
+        let n = Normal::new(4.0, 0.0, 0.0);
+        assert_eq!(n.norm(), Normal::new(1.0, 0.0, 0.0));
+        assert_eq!(n.dot(Vector::new(0.5, 1.0, 0.0)), 2.0);
+        assert_eq!(-n, Normal::new(-4.0, 0.0, 0.0));
+    }
 }
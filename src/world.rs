@@ -0,0 +1,431 @@
+use crate::color::{self, Color};
+use crate::rays::Ray;
+use crate::tup::Point;
+use crate::shape::Shape;
+use crate::light::{PointLight, lighting};
+use crate::intersection::*;
+
+// How many times a ray may bounce before it stops contributing.
+pub const MAX_DEPTH: usize = 5;
+
+#[derive(Debug, Default)]
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<PointLight>,
+}
+
+impl World {
+    pub fn new(objects: Vec<Box<dyn Shape>>, lights: Vec<PointLight>) -> World {
+        World{objects, lights}
+    }
+
+    pub fn intersect_world(&self, ray: Ray) -> Intersections<'_> {
+        let xs: Vec<Intersection> = self.objects.iter()
+            .flat_map(|o| o.intersect(ray).xs)
+            .collect();
+
+        Intersections::new(xs)
+    }
+
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        let material = comps.object.material();
+        let surface = self.lights.iter()
+            .map(|light| {
+                let shadowed = self.is_shadowed(comps.over_point, light);
+                lighting(material, comps.object, light,
+                         comps.over_point, comps.eyev, comps.normalv, shadowed)
+            })
+            .fold(color::BLACK, |acc, c| acc + c);
+
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return color::BLACK;
+        }
+
+        let color = self.color_at(Ray::new(comps.over_point, comps.reflectv), remaining - 1);
+        color * reflective
+    }
+
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let transparency = comps.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return color::BLACK;
+        }
+
+        // Snell's law, checking for total internal reflection.
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.normalv.dot(comps.eyev);
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        if sin2_t > 1.0 {
+            return color::BLACK;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv.to_vector() * (n_ratio * cos_i - cos_t)
+            - comps.eyev * n_ratio;
+
+        let color = self.color_at(Ray::new(comps.under_point, direction), remaining - 1);
+        color * transparency
+    }
+
+    pub fn is_shadowed(&self, point: Point, light: &PointLight) -> bool {
+        let v = light.position - point;
+        let distance = v.mag();
+        let r = Ray::new(point, v.norm());
+
+        match self.intersect_world(r).hit() {
+            Some(hit) => hit.t < distance,
+            None => false,
+        }
+    }
+
+    pub fn color_at(&self, ray: Ray, remaining: usize) -> Color {
+        let xs = self.intersect_world(ray);
+
+        match xs.hit() {
+            Some(hit) => self.shade_hit(&hit.prepare_computations(ray, &xs), remaining),
+            None => color::BLACK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::matrix::Matrix4x4;
+    use crate::plane::Plane;
+    use crate::tup::Vector;
+    use crate::sphere::Sphere;
+    use crate::transform::*;
+
+    fn default_world() -> World {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+        let s1 = Sphere::unit().with_material(Material {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Material::default()
+        });
+        let s2 = Sphere::new(scaling(0.5, 0.5, 0.5));
+
+        World::new(vec![Box::new(s1), Box::new(s2)], vec![light])
+    }
+
+    #[test]
+    fn empty() {
+        let w = World::default();
+
+        assert_eq!(w.objects.len(), 0);
+        assert_eq!(w.lights.len(), 0);
+    }
+
+    #[test]
+    fn intersect_world() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect_world(r);
+
+        assert_eq!(xs.xs.len(), 4);
+        assert_eq!(xs.xs[0].t, 4.0);
+        assert_eq!(xs.xs[1].t, 4.5);
+        assert_eq!(xs.xs[2].t, 5.5);
+        assert_eq!(xs.xs[3].t, 6.0);
+    }
+
+    #[test]
+    fn shade_hit() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shade_hit_inside() {
+        let mut w = default_world();
+        w.lights = vec![PointLight::new(Point::new(0.0, 0.25, 0.0),
+                                        Color::new(1.0, 1.0, 1.0))];
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, w.objects[1].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn shade_hit_lights() {
+        let mut w = default_world();
+        let light = w.lights[0];
+        w.lights.push(light);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855) * 2.0);
+    }
+
+    #[test]
+    fn color_at_miss() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 1.0, 0.0));
+
+        assert_eq!(w.color_at(r, MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn color_at_hit() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(r, MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn color_at_behind() {
+        let mut w = default_world();
+        let outer = w.objects[0].material().clone();
+        w.objects[0] = Box::new(Sphere::unit()
+                                .with_material(Material { ambient: 1.0, ..outer }));
+        let inner = w.objects[1].material().clone();
+        w.objects[1] = Box::new(Sphere::new(scaling(0.5, 0.5, 0.5))
+                                .with_material(Material { ambient: 1.0, ..inner }));
+        let r = Ray::new(Point::new(0.0, 0.0, 0.75),
+                         Vector::new(0.0, 0.0, -1.0));
+
+        assert_eq!(w.color_at(r, MAX_DEPTH), w.objects[1].material().color);
+    }
+
+    #[test]
+    fn not_shadowed_collinear() {
+        let w = default_world();
+        assert!(!w.is_shadowed(Point::new(0.0, 10.0, 0.0), &w.lights[0]));
+    }
+
+    #[test]
+    fn shadowed_object_between() {
+        let w = default_world();
+        assert!(w.is_shadowed(Point::new(10.0, -10.0, 10.0), &w.lights[0]));
+    }
+
+    #[test]
+    fn not_shadowed_behind_light() {
+        let w = default_world();
+        assert!(!w.is_shadowed(Point::new(-20.0, 20.0, -20.0), &w.lights[0]));
+    }
+
+    #[test]
+    fn not_shadowed_behind_point() {
+        let w = default_world();
+        assert!(!w.is_shadowed(Point::new(-2.0, 2.0, -2.0), &w.lights[0]));
+    }
+
+    #[test]
+    fn shade_hit_shadow() {
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0),
+                                    Color::new(1.0, 1.0, 1.0));
+        let s1 = Sphere::unit();
+        let s2 = Sphere::new(translation(0.0, 0.0, 10.0));
+        let w = World::new(vec![Box::new(s1), Box::new(s2)], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0),
+                         Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.1, 0.1, 0.1));
+    }
+
+    fn with_mirror_floor(mut w: World) -> World {
+        let floor = Plane::new(translation(0.0, -1.0, 0.0)).with_material(Material {
+            reflective: 0.5,
+            ..Material::default()
+        });
+        w.objects.push(Box::new(floor));
+        w
+    }
+
+    #[test]
+    fn reflected_color_nonreflective() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.reflected_color(&comps, MAX_DEPTH), color::BLACK);
+    }
+
+    #[test]
+    fn reflected_color() {
+        let w = with_mirror_floor(default_world());
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -a, a));
+        let i = Intersection::new(2.0_f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.reflected_color(&comps, MAX_DEPTH),
+                   Color::new(0.19032, 0.2379, 0.14274));
+        assert_eq!(w.reflected_color(&comps, 0), color::BLACK);
+    }
+
+    #[test]
+    fn shade_hit_reflective() {
+        let w = with_mirror_floor(default_world());
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -a, a));
+        let i = Intersection::new(2.0_f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.87677, 0.92436, 0.82918));
+    }
+
+    #[test]
+    fn parallel_mirrors() {
+        let mirror = Material { reflective: 1.0, ..Material::default() };
+        let lower = Plane::new(translation(0.0, -1.0, 0.0)).with_material(mirror.clone());
+        let upper = Plane::new(translation(0.0, 1.0, 0.0)).with_material(mirror);
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), color::WHITE);
+        let w = World::new(vec![Box::new(lower), Box::new(upper)], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+
+        // Each bounce adds the same ambient and diffuse light, so the depth
+        // limit is what stops the recursion.
+        let one = w.color_at(r, 0);
+        assert_eq!(w.color_at(r, 3), one * 4.0);
+    }
+
+    fn glass() -> Material {
+        Material {
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn refracted_color_opaque() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(4.0, w.objects[0].as_ref()),
+                                         Intersection::new(6.0, w.objects[0].as_ref())]);
+        let comps = xs.xs[0].prepare_computations(r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, MAX_DEPTH), color::BLACK);
+    }
+
+    fn with_glass_sphere() -> World {
+        let mut w = default_world();
+        w.objects[0] = Box::new(Sphere::unit().with_material(glass()));
+        w
+    }
+
+    #[test]
+    fn refracted_color_max_depth() {
+        let w = with_glass_sphere();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(4.0, w.objects[0].as_ref()),
+                                         Intersection::new(6.0, w.objects[0].as_ref())]);
+        let comps = xs.xs[0].prepare_computations(r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, 0), color::BLACK);
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        let w = with_glass_sphere();
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, a), Vector::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(-a, w.objects[0].as_ref()),
+                                         Intersection::new(a, w.objects[0].as_ref())]);
+        let comps = xs.xs[1].prepare_computations(r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, MAX_DEPTH), color::BLACK);
+    }
+
+    fn with_glass_floor(reflective: f32) -> World {
+        let mut w = default_world();
+        let floor = Plane::new(translation(0.0, -1.0, 0.0)).with_material(Material {
+            transparency: 0.5,
+            reflective,
+            ..glass()
+        });
+        let ball = Sphere::new(translation(0.0, -3.5, -0.5)).with_material(Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ambient: 0.5,
+            ..Material::default()
+        });
+        w.objects.push(Box::new(floor));
+        w.objects.push(Box::new(ball));
+        w
+    }
+
+    #[test]
+    fn shade_hit_transparent() {
+        let w = with_glass_floor(0.0);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -a, a));
+        let i = Intersection::new(2.0_f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shade_hit_fresnel() {
+        let w = with_glass_floor(0.5);
+        let a = 2.0_f32.sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -a, a));
+        let i = Intersection::new(2.0_f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(r, &Intersections::new(vec![i]));
+
+        assert_eq!(w.shade_hit(&comps, MAX_DEPTH), Color::new(0.93391, 0.69643, 0.69243));
+    }
+
+    #[test]
+    fn refraction_through_sphere() {
+        // A ray through the centre of a glass ball passes straight through
+        // and picks up the color behind it, less what the surface reflects.
+        let backdrop = Plane::new(Matrix4x4::identity()
+                                  .rotate_x(std::f32::consts::PI / 2.0)
+                                  .translate(0.0, 0.0, 5.0))
+            .with_material(Material {
+                color: Color::new(0.0, 1.0, 0.0),
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            });
+        let ball = Sphere::unit().with_material(Material {
+            color: color::BLACK,
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..glass()
+        });
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), color::WHITE);
+        let w = World::new(vec![Box::new(ball), Box::new(backdrop)], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(r, MAX_DEPTH), Color::new(0.0, 1.0, 0.0));
+    }
+}